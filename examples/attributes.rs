@@ -43,4 +43,14 @@ fn main() {
     println!("{:?}", dt.all_attributes());
 
     println!("{:?}", dt.get_vertex_attributes(4));
+
+    //-- typed access, without JSON
+    let _ = dt.set_vertex_attribute(
+        2,
+        "intensity",
+        startin::attributes::AttributeValue::F64(2.2),
+    );
+    println!("{:?}", dt.get_vertex_attribute(2, "intensity"));
+    let c = dt.attribute_column("intensity").unwrap();
+    println!("{:?}", c.as_f64());
 }
@@ -44,15 +44,12 @@ fn main() {
         locs.push([rx.sample(&mut rng), ry.sample(&mut rng)]);
     }
 
-    let idw = startin::interpolation::IDW {
-        radius: 1.0,
-        power: 2.0,
-    };
-    let re1 = startin::interpolation::interpolate(&idw, &mut dt, &locs);
+    let idw = startin::interpolation::IDW::new(1.0, 2.0);
+    let re1 = startin::interpolation::interpolate(&idw, &dt, &locs);
     // println!("{:?}", re.len());
 
     let lap = startin::interpolation::Laplace {};
-    let re2 = startin::interpolation::interpolate(&lap, &mut dt, &locs);
+    let re2 = startin::interpolation::interpolate(&lap, &dt, &locs);
     // println!("{:?}", re.len());
 
     for i in 0..re1.len() {
@@ -42,7 +42,7 @@ fn main() {
     //-- interpolate with Laplace interpolation at 2 locations
     let locs = vec![[51.0, 22.0], [50.3, 19.9]];
     let interpolant = startin::interpolation::Laplace {};
-    let zs = startin::interpolation::interpolate(&interpolant, &mut dt, &locs);
+    let zs = startin::interpolation::interpolate(&interpolant, &dt, &locs);
     for z in &zs {
         match z {
             Ok(value) => println!("z = {}", value),
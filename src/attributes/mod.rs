@@ -0,0 +1,722 @@
+//! # attributes
+//!
+//! Typed and columnar storage of the extra attributes of the vertices.
+//! Each attribute is stored in its own contiguous [`Vec`] (one entry per vertex, including
+//! the infinite one and the removed ones), and a mask tells whether a value was set for a given
+//! vertex or not.
+//! The JSON API of [`crate::Triangulation`] (eg `add_vertex_attributes()`) is built on top of this.
+//!
+//! Triangles and edges can also have attributes, these are stored only for the elements that
+//! have some, and the [`InheritanceRules`] define what happens to them when the triangulation
+//! is modified.
+
+use crate::StartinError;
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+/// The data types possible for an attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    F64,
+    I64,
+    U64,
+    Bool,
+    String,
+}
+
+impl AttributeType {
+    /// Returns the [`AttributeType`] from its name ("f64", "i64", "u64", "bool", or "String").
+    pub fn from_name(s: &str) -> Option<AttributeType> {
+        match s {
+            "f64" => Some(AttributeType::F64),
+            "i64" => Some(AttributeType::I64),
+            "u64" => Some(AttributeType::U64),
+            "bool" => Some(AttributeType::Bool),
+            "String" => Some(AttributeType::String),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the data type, as used in the schema.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            AttributeType::F64 => "f64",
+            AttributeType::I64 => "i64",
+            AttributeType::U64 => "u64",
+            AttributeType::Bool => "bool",
+            AttributeType::String => "String",
+        }
+    }
+}
+
+impl fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// One value of an attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    F64(f64),
+    I64(i64),
+    U64(u64),
+    Bool(bool),
+    String(String),
+}
+
+impl AttributeValue {
+    pub fn dtype(&self) -> AttributeType {
+        match *self {
+            AttributeValue::F64(_) => AttributeType::F64,
+            AttributeValue::I64(_) => AttributeType::I64,
+            AttributeValue::U64(_) => AttributeType::U64,
+            AttributeValue::Bool(_) => AttributeType::Bool,
+            AttributeValue::String(_) => AttributeType::String,
+        }
+    }
+
+    /// Returns the value as a f64, if it is numeric (a bool is 0.0 or 1.0).
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            AttributeValue::F64(v) => Some(v),
+            AttributeValue::I64(v) => Some(v as f64),
+            AttributeValue::U64(v) => Some(v as f64),
+            AttributeValue::Bool(v) => Some(if v { 1.0 } else { 0.0 }),
+            AttributeValue::String(_) => None,
+        }
+    }
+
+    /// Converts a JSON value to the given type; `None` if the JSON value
+    /// is not of that type (for "f64" every JSON number is accepted).
+    pub fn from_json(v: &Value, dtype: AttributeType) -> Option<AttributeValue> {
+        match dtype {
+            AttributeType::F64 => v.as_f64().map(AttributeValue::F64),
+            AttributeType::I64 => v.as_i64().map(AttributeValue::I64),
+            AttributeType::U64 => v.as_u64().map(AttributeValue::U64),
+            AttributeType::Bool => v.as_bool().map(AttributeValue::Bool),
+            AttributeType::String => v.as_str().map(|s| AttributeValue::String(s.to_string())),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            AttributeValue::F64(v) => json!(v),
+            AttributeValue::I64(v) => json!(v),
+            AttributeValue::U64(v) => json!(v),
+            AttributeValue::Bool(v) => json!(v),
+            AttributeValue::String(v) => json!(v),
+        }
+    }
+}
+
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeValue::F64(v) => write!(f, "{}", v),
+            AttributeValue::I64(v) => write!(f, "{}", v),
+            AttributeValue::U64(v) => write!(f, "{}", v),
+            AttributeValue::Bool(v) => write!(f, "{}", v),
+            AttributeValue::String(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// The values of one attribute for all the vertices, stored contiguously
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValues {
+    F64(Vec<f64>),
+    I64(Vec<i64>),
+    U64(Vec<u64>),
+    Bool(Vec<bool>),
+    String(Vec<String>),
+}
+
+impl ColumnValues {
+    fn new(dtype: AttributeType, len: usize) -> ColumnValues {
+        match dtype {
+            AttributeType::F64 => ColumnValues::F64(vec![0.0; len]),
+            AttributeType::I64 => ColumnValues::I64(vec![0; len]),
+            AttributeType::U64 => ColumnValues::U64(vec![0; len]),
+            AttributeType::Bool => ColumnValues::Bool(vec![false; len]),
+            AttributeType::String => ColumnValues::String(vec![String::new(); len]),
+        }
+    }
+
+    pub fn dtype(&self) -> AttributeType {
+        match *self {
+            ColumnValues::F64(_) => AttributeType::F64,
+            ColumnValues::I64(_) => AttributeType::I64,
+            ColumnValues::U64(_) => AttributeType::U64,
+            ColumnValues::Bool(_) => AttributeType::Bool,
+            ColumnValues::String(_) => AttributeType::String,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnValues::F64(x) => x.len(),
+            ColumnValues::I64(x) => x.len(),
+            ColumnValues::U64(x) => x.len(),
+            ColumnValues::Bool(x) => x.len(),
+            ColumnValues::String(x) => x.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get(&self, i: usize) -> AttributeValue {
+        match self {
+            ColumnValues::F64(x) => AttributeValue::F64(x[i]),
+            ColumnValues::I64(x) => AttributeValue::I64(x[i]),
+            ColumnValues::U64(x) => AttributeValue::U64(x[i]),
+            ColumnValues::Bool(x) => AttributeValue::Bool(x[i]),
+            ColumnValues::String(x) => AttributeValue::String(x[i].clone()),
+        }
+    }
+
+    fn set(&mut self, i: usize, v: AttributeValue) -> bool {
+        match (self, v) {
+            (ColumnValues::F64(x), AttributeValue::F64(v)) => x[i] = v,
+            (ColumnValues::I64(x), AttributeValue::I64(v)) => x[i] = v,
+            (ColumnValues::U64(x), AttributeValue::U64(v)) => x[i] = v,
+            (ColumnValues::Bool(x), AttributeValue::Bool(v)) => x[i] = v,
+            (ColumnValues::String(x), AttributeValue::String(v)) => x[i] = v,
+            _ => return false,
+        }
+        true
+    }
+
+    fn push_default(&mut self) {
+        match self {
+            ColumnValues::F64(x) => x.push(0.0),
+            ColumnValues::I64(x) => x.push(0),
+            ColumnValues::U64(x) => x.push(0),
+            ColumnValues::Bool(x) => x.push(false),
+            ColumnValues::String(x) => x.push(String::new()),
+        }
+    }
+
+    fn reset(&mut self, i: usize) {
+        match self {
+            ColumnValues::F64(x) => x[i] = 0.0,
+            ColumnValues::I64(x) => x[i] = 0,
+            ColumnValues::U64(x) => x[i] = 0,
+            ColumnValues::Bool(x) => x[i] = false,
+            ColumnValues::String(x) => x[i].clear(),
+        }
+    }
+
+    fn remove(&mut self, i: usize) {
+        match self {
+            ColumnValues::F64(x) => {
+                x.remove(i);
+            }
+            ColumnValues::I64(x) => {
+                x.remove(i);
+            }
+            ColumnValues::U64(x) => {
+                x.remove(i);
+            }
+            ColumnValues::Bool(x) => {
+                x.remove(i);
+            }
+            ColumnValues::String(x) => {
+                x.remove(i);
+            }
+        }
+    }
+}
+
+/// One attribute (a column): its name, its values, and for each vertex whether
+/// a value was set (the unset ones have the default value of the type: 0, false, or "")
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeColumn {
+    name: String,
+    values: ColumnValues,
+    set: Vec<bool>,
+}
+
+impl AttributeColumn {
+    fn new(name: &str, dtype: AttributeType, len: usize) -> AttributeColumn {
+        AttributeColumn {
+            name: name.to_string(),
+            values: ColumnValues::new(dtype, len),
+            set: vec![false; len],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn dtype(&self) -> AttributeType {
+        self.values.dtype()
+    }
+
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Returns all the values (one per vertex)
+    pub fn values(&self) -> &ColumnValues {
+        &self.values
+    }
+
+    /// Returns for each vertex whether a value was set
+    pub fn mask(&self) -> &[bool] {
+        &self.set
+    }
+
+    pub fn is_set(&self, i: usize) -> bool {
+        i < self.set.len() && self.set[i]
+    }
+
+    /// Returns the value for the vertex `i`, `None` if it was not set.
+    pub fn get(&self, i: usize) -> Option<AttributeValue> {
+        match self.is_set(i) {
+            true => Some(self.values.get(i)),
+            false => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<&[f64]> {
+        match &self.values {
+            ColumnValues::F64(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<&[i64]> {
+        match &self.values {
+            ColumnValues::I64(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<&[u64]> {
+        match &self.values {
+            ColumnValues::U64(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<&[bool]> {
+        match &self.values {
+            ColumnValues::Bool(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&[String]> {
+        match &self.values {
+            ColumnValues::String(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    fn set_value(&mut self, i: usize, v: AttributeValue) -> bool {
+        if self.values.set(i, v) {
+            self.set[i] = true;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The columnar storage of the attributes of all the vertices
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AttributeStore {
+    columns: Vec<AttributeColumn>,
+    len: usize,
+}
+
+impl AttributeStore {
+    pub(crate) fn new(schema: &[(String, AttributeType)], len: usize) -> AttributeStore {
+        let mut columns: Vec<AttributeColumn> = Vec::new();
+        for (name, dtype) in schema {
+            columns.push(AttributeColumn::new(name, *dtype, len));
+        }
+        AttributeStore { columns, len }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn schema(&self) -> Vec<(String, AttributeType)> {
+        self.columns
+            .iter()
+            .map(|c| (c.name.clone(), c.dtype()))
+            .collect()
+    }
+
+    pub(crate) fn columns(&self) -> &[AttributeColumn] {
+        &self.columns
+    }
+
+    pub(crate) fn column(&self, name: &str) -> Option<&AttributeColumn> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    fn column_mut(&mut self, name: &str) -> Option<&mut AttributeColumn> {
+        self.columns.iter_mut().find(|c| c.name == name)
+    }
+
+    /// Adds one row (a new vertex), nothing is set
+    pub(crate) fn push_row(&mut self) {
+        for c in self.columns.iter_mut() {
+            c.values.push_default();
+            c.set.push(false);
+        }
+        self.len += 1;
+    }
+
+    /// Unsets all the attributes of the row `i`
+    pub(crate) fn reset_row(&mut self, i: usize) {
+        for c in self.columns.iter_mut() {
+            c.values.reset(i);
+            c.set[i] = false;
+        }
+    }
+
+    pub(crate) fn remove_row(&mut self, i: usize) {
+        for c in self.columns.iter_mut() {
+            c.values.remove(i);
+            c.set.remove(i);
+        }
+        self.len -= 1;
+    }
+
+    pub(crate) fn get(&self, i: usize, name: &str) -> Result<Option<AttributeValue>, StartinError> {
+        match self.column(name) {
+            Some(c) => Ok(c.get(i)),
+            None => Err(StartinError::WrongAttribute),
+        }
+    }
+
+    pub(crate) fn set(
+        &mut self,
+        i: usize,
+        name: &str,
+        v: AttributeValue,
+    ) -> Result<(), StartinError> {
+        match self.column_mut(name) {
+            Some(c) => match c.set_value(i, v) {
+                true => Ok(()),
+                false => Err(StartinError::WrongAttribute),
+            },
+            None => Err(StartinError::WrongAttribute),
+        }
+    }
+
+    /// Adds a new column at the end; if `default` is given then all the rows
+    /// get it (except those in `unset`), otherwise they are all unset.
+    /// If a column with the same name and type exists nothing is done, if the type
+    /// is different then a [`StartinError::WrongAttribute`] is returned.
+    pub(crate) fn add_column(
+        &mut self,
+        name: &str,
+        dtype: AttributeType,
+        default: Option<AttributeValue>,
+        unset: &[usize],
+    ) -> Result<(), StartinError> {
+        if let Some(d) = &default {
+            if d.dtype() != dtype {
+                return Err(StartinError::WrongAttribute);
+            }
+        }
+        if let Some(c) = self.column(name) {
+            return match c.dtype() == dtype {
+                true => Ok(()),
+                false => Err(StartinError::WrongAttribute),
+            };
+        }
+        let mut c = AttributeColumn::new(name, dtype, self.len);
+        if let Some(d) = default {
+            let unset: HashSet<usize> = unset.iter().copied().collect();
+            for i in 0..self.len {
+                if !unset.contains(&i) {
+                    c.set_value(i, d.clone());
+                }
+            }
+        }
+        self.columns.push(c);
+        Ok(())
+    }
+
+    pub(crate) fn remove_column(&mut self, name: &str) -> Result<(), StartinError> {
+        match self.columns.iter().position(|c| c.name == name) {
+            Some(i) => {
+                self.columns.remove(i);
+                Ok(())
+            }
+            None => Err(StartinError::WrongAttribute),
+        }
+    }
+
+    pub(crate) fn rename_column(&mut self, name: &str, newname: &str) -> Result<(), StartinError> {
+        if name != newname && self.column(newname).is_some() {
+            return Err(StartinError::WrongAttribute);
+        }
+        match self.column_mut(name) {
+            Some(c) => {
+                c.name = newname.to_string();
+                Ok(())
+            }
+            None => Err(StartinError::WrongAttribute),
+        }
+    }
+
+    /// Replaces the schema: the columns that are kept (same name and type) keep their
+    /// values, the new ones are unset, and the ones not in `schema` are dropped.
+    /// If a column changes type then a [`StartinError::WrongAttribute`] is returned
+    /// and nothing is modified.
+    pub(crate) fn set_schema(
+        &mut self,
+        schema: &[(String, AttributeType)],
+    ) -> Result<(), StartinError> {
+        for (name, dtype) in schema {
+            if let Some(c) = self.column(name) {
+                if c.dtype() != *dtype {
+                    return Err(StartinError::WrongAttribute);
+                }
+            }
+        }
+        let mut columns: Vec<AttributeColumn> = Vec::new();
+        for (name, dtype) in schema {
+            match self.columns.iter().position(|c| c.name == *name) {
+                Some(i) => columns.push(self.columns.swap_remove(i)),
+                None => columns.push(AttributeColumn::new(name, *dtype, self.len)),
+            }
+        }
+        self.columns = columns;
+        Ok(())
+    }
+
+    /// Replaces all the values of a column, they are all flagged as set
+    /// (except those in `unset`, eg the infinite vertex and the removed ones)
+    pub(crate) fn set_column(
+        &mut self,
+        name: &str,
+        values: ColumnValues,
+        unset: &[usize],
+    ) -> Result<(), StartinError> {
+        let len = self.len;
+        match self.column_mut(name) {
+            Some(c) => {
+                if values.dtype() != c.dtype() || values.len() != len {
+                    return Err(StartinError::WrongAttribute);
+                }
+                c.values = values;
+                c.set = vec![true; len];
+                for i in unset {
+                    if *i < len {
+                        c.values.reset(*i);
+                        c.set[*i] = false;
+                    }
+                }
+                Ok(())
+            }
+            None => Err(StartinError::WrongAttribute),
+        }
+    }
+
+    /// Returns the row `i` as a JSON object, only the attributes set are present
+    pub(crate) fn get_json(&self, i: usize) -> Value {
+        let mut m: Map<String, Value> = Map::new();
+        for c in &self.columns {
+            if let Some(v) = c.get(i) {
+                m.insert(c.name.clone(), v.to_json());
+            }
+        }
+        Value::Object(m)
+    }
+
+    /// Sets the row `i` from a JSON object; the members that are not in the
+    /// schema or that have the wrong type are ignored.
+    /// Returns `false` if `a` is not a JSON object.
+    pub(crate) fn set_json(&mut self, i: usize, a: &Value) -> bool {
+        let a1 = match a.as_object() {
+            Some(x) => x,
+            None => return false,
+        };
+        for (p, v) in a1 {
+            if let Some(c) = self.column_mut(p) {
+                if let Some(av) = AttributeValue::from_json(v, c.dtype()) {
+                    c.set_value(i, av);
+                }
+            }
+        }
+        true
+    }
+}
+
+/// What the new triangles created by a flip get from the triangles that were
+/// removed by that flip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriangleInheritance {
+    /// the attributes are dropped, the new triangles have none
+    Drop,
+    /// the new triangles get the attributes of the old triangle that they overlap the most
+    /// (for a flip13 this is the triangle that is split)
+    LargestOverlap,
+    /// the new triangles get the attributes only if all the old triangles had the
+    /// same ones (for a flip13 this is the same as [`TriangleInheritance::LargestOverlap`])
+    IfEqual,
+}
+
+/// Rules defining what happens to the attributes of the triangles and of the
+/// edges when the triangulation is modified by flips.
+/// When a vertex is removed, the attributes of its incident triangles and edges
+/// are always dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InheritanceRules {
+    /// rule for the 3 triangles created by the insertion of a vertex in a triangle
+    pub flip13: TriangleInheritance,
+    /// rule for the 2 triangles created when the diagonal of a quadrilateral is flipped
+    pub flip22: TriangleInheritance,
+    /// if an edge is split in 2 by the insertion of a vertex on it,
+    /// do the 2 new edges get its attributes?
+    pub split_edges: bool,
+}
+
+impl Default for InheritanceRules {
+    fn default() -> Self {
+        InheritanceRules {
+            flip13: TriangleInheritance::LargestOverlap,
+            flip22: TriangleInheritance::LargestOverlap,
+            split_edges: true,
+        }
+    }
+}
+
+pub(crate) type AttributeRow = Vec<Option<AttributeValue>>;
+
+/// The attributes of elements identified by a key (the triangles and the edges),
+/// only the elements having at least one attribute set are stored
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeyedAttributes<K: Eq + Hash + Copy> {
+    schema: Vec<(String, AttributeType)>,
+    rows: HashMap<K, AttributeRow>,
+}
+
+impl<K: Eq + Hash + Copy> KeyedAttributes<K> {
+    pub(crate) fn new() -> KeyedAttributes<K> {
+        KeyedAttributes {
+            schema: Vec::new(),
+            rows: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn schema(&self) -> &[(String, AttributeType)] {
+        &self.schema
+    }
+
+    pub(crate) fn has_schema(&self) -> bool {
+        !self.schema.is_empty()
+    }
+
+    /// No element has attributes
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Replaces the schema, same behaviour as [`AttributeStore::set_schema()`]
+    pub(crate) fn set_schema(
+        &mut self,
+        schema: &[(String, AttributeType)],
+    ) -> Result<(), StartinError> {
+        let mut mapping: Vec<Option<usize>> = Vec::new();
+        for (name, dtype) in schema {
+            match self.schema.iter().position(|(n, _)| n == name) {
+                Some(i) => {
+                    if self.schema[i].1 != *dtype {
+                        return Err(StartinError::WrongAttribute);
+                    }
+                    mapping.push(Some(i));
+                }
+                None => mapping.push(None),
+            }
+        }
+        for row in self.rows.values_mut() {
+            *row = mapping
+                .iter()
+                .map(|m| m.and_then(|i| row[i].clone()))
+                .collect();
+        }
+        self.rows.retain(|_, row| row.iter().any(|v| v.is_some()));
+        self.schema = schema.to_vec();
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, k: &K, name: &str) -> Result<Option<AttributeValue>, StartinError> {
+        match self.schema.iter().position(|(n, _)| n == name) {
+            Some(i) => Ok(self.rows.get(k).and_then(|row| row[i].clone())),
+            None => Err(StartinError::WrongAttribute),
+        }
+    }
+
+    pub(crate) fn set(&mut self, k: K, name: &str, v: AttributeValue) -> Result<(), StartinError> {
+        match self.schema.iter().position(|(n, _)| n == name) {
+            Some(i) => {
+                if self.schema[i].1 != v.dtype() {
+                    return Err(StartinError::WrongAttribute);
+                }
+                let l = self.schema.len();
+                self.rows.entry(k).or_insert_with(|| vec![None; l])[i] = Some(v);
+                Ok(())
+            }
+            None => Err(StartinError::WrongAttribute),
+        }
+    }
+
+    pub(crate) fn get_json(&self, k: &K) -> Value {
+        let mut m: Map<String, Value> = Map::new();
+        if let Some(row) = self.rows.get(k) {
+            for (i, (name, _)) in self.schema.iter().enumerate() {
+                if let Some(v) = &row[i] {
+                    m.insert(name.clone(), v.to_json());
+                }
+            }
+        }
+        Value::Object(m)
+    }
+
+    pub(crate) fn row(&self, k: &K) -> Option<&AttributeRow> {
+        self.rows.get(k)
+    }
+
+    pub(crate) fn take(&mut self, k: &K) -> Option<AttributeRow> {
+        self.rows.remove(k)
+    }
+
+    pub(crate) fn insert_row(&mut self, k: K, row: AttributeRow) {
+        self.rows.insert(k, row);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.rows.clear();
+    }
+
+    /// Changes all the keys, used when the vertices get new IDs
+    pub(crate) fn remap<F: Fn(&K) -> K>(&mut self, f: F) {
+        let rows = std::mem::take(&mut self.rows);
+        for (k, row) in rows {
+            self.rows.insert(f(&k), row);
+        }
+    }
+}
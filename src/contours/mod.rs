@@ -0,0 +1,310 @@
+//! # contours
+//!
+//! Extraction of contour lines (isolines) and of filled contours (isobands) from the
+//! triangulation.
+//!
+//! A vertex whose z-value is exactly at a level is considered to be above that level
+//! (symbolic perturbation), so that a triangle is always intersected by at most one segment
+//! and no segment is duplicated; the contour lines pass exactly through those vertices.
+
+use crate::StartinError;
+use crate::Triangulation;
+
+use crate::geom;
+
+use std::collections::HashMap;
+
+/// A contour line: its points are at the z-value of its level, and the higher ground is
+/// always on its left (a contour line around a hill is thus CCW).
+/// A contour line is closed if it is inside the convex hull (then the first point is not
+/// repeated at the end), and open if it starts and ends on the convex hull.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContourLine {
+    pub level: f64,
+    pub points: Vec<[f64; 3]>,
+    pub closed: bool,
+}
+
+/// Returns the contour lines of all the `levels`, the segments of each triangle are stitched
+/// into polylines (see [`ContourLine`]).
+/// Returns [`StartinError::EmptyTriangulation`] if the triangulation is not initialised.
+pub fn contour_lines(dt: &Triangulation, levels: &[f64]) -> Result<Vec<ContourLine>, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let trs = geom::finite_triangles(dt);
+    let mut re: Vec<ContourLine> = Vec::new();
+    for level in levels {
+        //-- each segment is stored with the (directed) edges where it starts and ends,
+        //-- it starts where the boundary of the triangle (CCW) goes from above to below
+        let mut segments: HashMap<[usize; 2], [usize; 2]> = HashMap::new();
+        for tr in &trs {
+            let above: Vec<bool> = tr.iter().map(|v| dt.stars[*v].pt[2] >= *level).collect();
+            let mut start: Option<[usize; 2]> = None;
+            let mut end: Option<[usize; 2]> = None;
+            for i in 0..3 {
+                let j = (i + 1) % 3;
+                if above[i] && !above[j] {
+                    start = Some([tr[i], tr[j]]);
+                } else if !above[i] && above[j] {
+                    end = Some([tr[i], tr[j]]);
+                }
+            }
+            if let (Some(s), Some(e)) = (start, end) {
+                segments.insert(s, e);
+            }
+        }
+        //-- the open lines start on the convex hull, the edge is not the end of a segment
+        let mut starts: Vec<[usize; 2]> = segments
+            .keys()
+            .filter(|s| dt.stars[s[1]].link.get_next_vertex(s[0]) == Some(0))
+            .copied()
+            .collect();
+        starts.sort_unstable();
+        for s in starts {
+            let edges = stitch(&mut segments, s);
+            push_contour(dt, &mut re, *level, &edges, false);
+        }
+        //-- the rest are closed lines
+        let mut rest: Vec<[usize; 2]> = segments.keys().copied().collect();
+        rest.sort_unstable();
+        for s in rest {
+            if segments.contains_key(&s) {
+                let mut edges = stitch(&mut segments, s);
+                edges.pop();
+                push_contour(dt, &mut re, *level, &edges, true);
+            }
+        }
+    }
+    Ok(re)
+}
+
+/// The polygons of the area whose elevation is in the band [`lower`, `upper`).
+/// Each polygon is a list of rings, the first one is the exterior ring (CCW) and the others
+/// are the holes (CW); the rings are closed (the first point is repeated at the end), as in
+/// GeoJSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Isoband {
+    pub lower: f64,
+    pub upper: f64,
+    pub polygons: Vec<Vec<Vec<[f64; 3]>>>,
+}
+
+/// A point of the boundary of a band: a vertex of the triangulation, or the intersection of
+/// an edge (smallest vertex first) with a level (its index)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Node {
+    Vertex(usize),
+    Crossing(usize, usize, usize),
+}
+
+/// Returns the isobands between the consecutive `breakpoints` (they are sorted first), one
+/// [`Isoband`] per band (even if it is empty).
+/// The part of each triangle inside a band is computed, and the edges shared by two of those
+/// parts are removed, the remaining ones form the rings of the band.
+/// Returns [`StartinError::EmptyTriangulation`] if the triangulation is not initialised.
+pub fn isobands(dt: &Triangulation, breakpoints: &[f64]) -> Result<Vec<Isoband>, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let mut levels = breakpoints.to_vec();
+    levels.sort_by(|a, b| a.total_cmp(b));
+    levels.dedup();
+    let trs = geom::finite_triangles(dt);
+    let mut re: Vec<Isoband> = Vec::new();
+    for band in 1..levels.len() {
+        let class = |v: usize| -> usize {
+            let z = dt.stars[v].pt[2];
+            if z < levels[band - 1] {
+                0
+            } else if z < levels[band] {
+                1
+            } else {
+                2
+            }
+        };
+        //-- the boundary edges (CCW around the band), those of adjacent parts cancel out:
+        //-- the count of an edge (smallest node first) is +1 for each time it is used in
+        //-- that direction and -1 in the other (the degenerate parts can use both)
+        let mut edges: HashMap<(Node, Node), i32> = HashMap::new();
+        for tr in &trs {
+            let cl: Vec<usize> = tr.iter().map(|v| class(*v)).collect();
+            if cl.iter().all(|c| *c == 0) || cl.iter().all(|c| *c == 2) {
+                continue;
+            }
+            let mut poly: Vec<Node> = Vec::new();
+            for i in 0..3 {
+                let j = (i + 1) % 3;
+                if cl[i] == 1 {
+                    poly.push(Node::Vertex(tr[i]));
+                }
+                //-- the levels crossed by the edge, in order
+                let crossed: Vec<usize> = if cl[i] < cl[j] {
+                    (cl[i]..cl[j]).map(|c| band - 1 + c).collect()
+                } else {
+                    (cl[j]..cl[i]).rev().map(|c| band - 1 + c).collect()
+                };
+                for l in crossed {
+                    poly.push(crossing_node(dt, tr[i], tr[j], l, &levels));
+                }
+            }
+            for i in 0..poly.len() {
+                let (a, b) = (poly[i], poly[(i + 1) % poly.len()]);
+                if a == b {
+                    continue;
+                }
+                if a < b {
+                    *edges.entry((a, b)).or_insert(0) += 1;
+                } else {
+                    *edges.entry((b, a)).or_insert(0) -= 1;
+                }
+            }
+        }
+        let mut outgoing: HashMap<Node, Vec<Node>> = HashMap::new();
+        let mut starts: Vec<(Node, Node)> = Vec::new();
+        for ((a, b), count) in edges {
+            for _ in 0..count.abs() {
+                starts.push(if count > 0 { (a, b) } else { (b, a) });
+            }
+        }
+        starts.sort_unstable();
+        for (a, b) in &starts {
+            outgoing.entry(*a).or_default().push(*b);
+        }
+        //-- the rings, at a node where the band touches itself the leftmost turn is taken
+        let mut rings: Vec<geom::Ring> = Vec::new();
+        for (a, b) in starts {
+            if !outgoing[&a].contains(&b) {
+                continue;
+            }
+            remove_edge(&mut outgoing, a, b);
+            let mut ring: Vec<Node> = vec![a];
+            let (mut prev, mut cur) = (a, b);
+            while cur != a {
+                ring.push(cur);
+                let next = leftmost_turn(dt, &levels, prev, cur, &outgoing[&cur]);
+                remove_edge(&mut outgoing, cur, next);
+                prev = cur;
+                cur = next;
+            }
+            let mut pts: Vec<[f64; 3]> = ring.iter().map(|n| node_point(dt, &levels, n)).collect();
+            pts.push(pts[0]);
+            rings.push(pts);
+        }
+        re.push(Isoband {
+            lower: levels[band - 1],
+            upper: levels[band],
+            polygons: geom::assemble_polygons(rings),
+        });
+    }
+    Ok(re)
+}
+
+/// The node of the intersection of the edge with the level `l`, it is the vertex itself if
+/// the vertex above is exactly at the level
+fn crossing_node(dt: &Triangulation, a: usize, b: usize, l: usize, levels: &[f64]) -> Node {
+    let upper = if dt.stars[a].pt[2] > dt.stars[b].pt[2] {
+        a
+    } else {
+        b
+    };
+    if dt.stars[upper].pt[2] == levels[l] {
+        Node::Vertex(upper)
+    } else {
+        Node::Crossing(a.min(b), a.max(b), l)
+    }
+}
+
+fn node_point(dt: &Triangulation, levels: &[f64], n: &Node) -> [f64; 3] {
+    match n {
+        Node::Vertex(v) => dt.stars[*v].pt,
+        Node::Crossing(a, b, l) => intersection(dt, &[*a, *b], levels[*l]),
+    }
+}
+
+fn remove_edge(outgoing: &mut HashMap<Node, Vec<Node>>, a: Node, b: Node) {
+    let out = outgoing.get_mut(&a).unwrap();
+    let i = out.iter().position(|n| *n == b).unwrap();
+    out.remove(i);
+}
+
+/// Among the `candidates`, the node that is the first clockwise from the direction cur->prev
+fn leftmost_turn(
+    dt: &Triangulation,
+    levels: &[f64],
+    prev: Node,
+    cur: Node,
+    candidates: &[Node],
+) -> Node {
+    if candidates.len() == 1 {
+        return candidates[0];
+    }
+    let c = node_point(dt, levels, &cur);
+    let p = node_point(dt, levels, &prev);
+    let back = (p[1] - c[1]).atan2(p[0] - c[0]);
+    let clockwise_angle = |n: &Node| {
+        let q = node_point(dt, levels, n);
+        let a = back - (q[1] - c[1]).atan2(q[0] - c[0]);
+        a.rem_euclid(2.0 * std::f64::consts::PI)
+    };
+    *candidates
+        .iter()
+        .min_by(|a, b| clockwise_angle(a).total_cmp(&clockwise_angle(b)))
+        .unwrap()
+}
+
+/// Follows the segments from the edge `s` (the edges are removed from `segments`), the edge
+/// where one segment ends is the start of the next one in the adjacent triangle (reversed)
+fn stitch(segments: &mut HashMap<[usize; 2], [usize; 2]>, s: [usize; 2]) -> Vec<[usize; 2]> {
+    let mut edges: Vec<[usize; 2]> = vec![s];
+    let mut cur = s;
+    while let Some(e) = segments.remove(&cur) {
+        edges.push(e);
+        cur = [e[1], e[0]];
+    }
+    edges
+}
+
+/// Converts the edges to points and adds the contour line, unless it collapses to
+/// one point (eg a summit exactly at the level)
+fn push_contour(
+    dt: &Triangulation,
+    re: &mut Vec<ContourLine>,
+    level: f64,
+    edges: &[[usize; 2]],
+    closed: bool,
+) {
+    let mut points: Vec<[f64; 3]> = Vec::new();
+    for e in edges {
+        let p = intersection(dt, e, level);
+        //-- the segments in the triangles incident to a vertex at the level have a length of 0
+        if points.last() != Some(&p) {
+            points.push(p);
+        }
+    }
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() > 1 {
+        re.push(ContourLine {
+            level,
+            points,
+            closed,
+        });
+    }
+}
+
+/// The intersection of the edge with the level, always computed from the vertex below
+/// so that it is the same for both triangles incident to the edge
+fn intersection(dt: &Triangulation, e: &[usize; 2], level: f64) -> [f64; 3] {
+    let (a, b) = if dt.stars[e[0]].pt[2] < dt.stars[e[1]].pt[2] {
+        (&dt.stars[e[0]].pt, &dt.stars[e[1]].pt)
+    } else {
+        (&dt.stars[e[1]].pt, &dt.stars[e[0]].pt)
+    };
+    if b[2] == level {
+        return [b[0], b[1], level];
+    }
+    let t = (level - a[2]) / (b[2] - a[2]);
+    [a[0] + t * (b[0] - a[0]), a[1] + t * (b[1] - a[1]), level]
+}
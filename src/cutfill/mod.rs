@@ -0,0 +1,320 @@
+//! # cutfill
+//!
+//! Cut and fill (the volume difference) between two triangulations, for instance a terrain
+//! surveyed before and after earthworks.
+//!
+//! The two TINs are overlaid: each triangle of one is clipped with the triangles of the other
+//! that overlap it, and in each piece of the overlay the difference of the two surfaces is
+//! linear, so the volumes and the areas are exact (up to the floating-point arithmetic).
+
+use crate::StartinError;
+use crate::Triangulation;
+
+use crate::geom;
+
+use std::collections::HashMap;
+
+/// The cut and fill volumes and areas, see [`cut_fill()`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CutFill {
+    /// the volume where `after` is below `before` (material removed)
+    pub cut_volume: f64,
+    /// the volume where `after` is above `before` (material added)
+    pub fill_volume: f64,
+    /// the area (in 2D) where `after` is below `before`
+    pub cut_area: f64,
+    /// the area (in 2D) where `after` is above `before`
+    pub fill_area: f64,
+    /// the area (in 2D) where the two surfaces are equal (up to the rounding errors)
+    pub unchanged_area: f64,
+    /// the area (in 2D) of the common domain (the intersection of the convex hulls), the sum
+    /// of the cut, fill and unchanged areas
+    pub area: f64,
+}
+
+impl CutFill {
+    /// The net volume (fill - cut)
+    pub fn net_volume(&self) -> f64 {
+        self.fill_volume - self.cut_volume
+    }
+}
+
+/// Computes the cut and fill volumes and areas between the surfaces `before` and `after`,
+/// over their common domain (the intersection of their convex hulls).
+/// Where the two surfaces are equal the area is neither cut nor fill, it is unchanged; a
+/// difference smaller than 1e-9 times the largest absolute z-value of the two
+/// triangulations is considered to be a rounding error.
+/// Returns [`StartinError::EmptyTriangulation`] if one of them is not initialised.
+pub fn cut_fill(before: &Triangulation, after: &Triangulation) -> Result<CutFill, StartinError> {
+    let pieces = overlay(before, after)?;
+    let zmax = before.stars[1..]
+        .iter()
+        .chain(after.stars[1..].iter())
+        .fold(0.0_f64, |m, s| m.max(s.pt[2].abs()));
+    let tol = 1e-9 * zmax;
+    let mut re = CutFill {
+        cut_volume: 0.0,
+        fill_volume: 0.0,
+        cut_area: 0.0,
+        fill_area: 0.0,
+        unchanged_area: 0.0,
+        area: 0.0,
+    };
+    for piece in pieces {
+        let area = polygon_area(&piece);
+        re.area += area;
+        if piece.iter().all(|p| p[2].abs() <= tol) {
+            re.unchanged_area += area;
+            continue;
+        }
+        //-- the difference is linear in the piece, so where it is 0.0 has no area
+        let fill = clip_positive(&piece, 1.0);
+        re.fill_area += polygon_area(&fill);
+        re.fill_volume += polygon_volume(&fill);
+        let cut = clip_positive(&piece, -1.0);
+        re.cut_area += polygon_area(&cut);
+        re.cut_volume -= polygon_volume(&cut);
+    }
+    Ok(re)
+}
+
+/// The difference TIN (`after` - `before`), see [`difference_tin()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifferenceTin {
+    /// the vertices, their z-value is the difference of the two surfaces
+    pub vertices: Vec<[f64; 3]>,
+    /// the triangles (CCW), with the indices of their vertices in `vertices`
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl DifferenceTin {
+    /// The area (in 2D) of the triangles
+    pub fn area(&self) -> f64 {
+        self.triangles
+            .iter()
+            .map(|t| {
+                geom::area2d_triangle(
+                    &self.vertices[t[0]],
+                    &self.vertices[t[1]],
+                    &self.vertices[t[2]],
+                )
+            })
+            .sum()
+    }
+
+    /// The volume between the triangles and z=0 (negative below), that is the net volume
+    /// (see [`CutFill::net_volume()`])
+    pub fn volume(&self) -> f64 {
+        self.triangles
+            .iter()
+            .map(|t| {
+                let (a, b, c) = (
+                    &self.vertices[t[0]],
+                    &self.vertices[t[1]],
+                    &self.vertices[t[2]],
+                );
+                geom::area2d_triangle(a, b, c) * (a[2] + b[2] + c[2]) / 3.0
+            })
+            .sum()
+    }
+}
+
+/// Returns the difference TIN (`after` - `before`) over the common domain: its triangles are
+/// the pieces of the overlay (each triangulated), so the difference is exact in each of them.
+/// Its vertices are those of both triangulations and the intersections of their edges, with
+/// the difference of the z-values of the two surfaces; the vertices shared by adjacent pieces
+/// are merged. It is not a Delaunay triangulation.
+/// Returns [`StartinError::EmptyTriangulation`] if one of them is not initialised.
+pub fn difference_tin(
+    before: &Triangulation,
+    after: &Triangulation,
+) -> Result<DifferenceTin, StartinError> {
+    let pieces = overlay(before, after)?;
+    //-- the same point computed in adjacent pieces differs only by the rounding errors
+    let bbox = after.get_bbox();
+    let tol = 1e-9 * ((bbox[2] - bbox[0]).powi(2) + (bbox[3] - bbox[1]).powi(2)).sqrt();
+    let cell = |p: &[f64; 3]| ((p[0] / tol).floor() as i64, (p[1] / tol).floor() as i64);
+    let mut index: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    let mut re = DifferenceTin {
+        vertices: Vec::new(),
+        triangles: Vec::new(),
+    };
+    for piece in pieces {
+        let mut ids: Vec<usize> = Vec::new();
+        for p in &piece {
+            let c = cell(p);
+            let found = (c.0 - 1..=c.0 + 1)
+                .flat_map(|cx| (c.1 - 1..=c.1 + 1).map(move |cy| (cx, cy)))
+                .filter_map(|k| index.get(&k))
+                .flatten()
+                .find(|i| geom::distance2d_squared(&re.vertices[**i], p) <= tol * tol)
+                .copied();
+            let id = match found {
+                Some(i) => i,
+                None => {
+                    re.vertices.push(*p);
+                    index.entry(c).or_default().push(re.vertices.len() - 1);
+                    re.vertices.len() - 1
+                }
+            };
+            if ids.last() != Some(&id) && ids.first() != Some(&id) {
+                ids.push(id);
+            }
+        }
+        for i in 1..ids.len().saturating_sub(1) {
+            let t = [ids[0], ids[i], ids[i + 1]];
+            let area =
+                geom::area2d_triangle(&re.vertices[t[0]], &re.vertices[t[1]], &re.vertices[t[2]]);
+            if area > 0.0 {
+                re.triangles.push(t);
+            }
+        }
+    }
+    Ok(re)
+}
+
+/// The pieces of the overlay of the two triangulations: convex polygons (CCW) where each point
+/// has the difference `after` - `before` as z-value
+fn overlay(
+    before: &Triangulation,
+    after: &Triangulation,
+) -> Result<Vec<Vec<[f64; 3]>>, StartinError> {
+    if !before.is_init || !after.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let trs_a = triangles(before);
+    let trs_b = triangles(after);
+    //-- a grid with the triangles of `after` overlapping each cell (with their bbox)
+    let bbox = after.get_bbox();
+    let cellsize = ((bbox[2] - bbox[0]) * (bbox[3] - bbox[1]) / trs_b.len() as f64).sqrt();
+    let cell = |x: f64, y: f64| {
+        (
+            ((x - bbox[0]) / cellsize).floor() as i64,
+            ((y - bbox[1]) / cellsize).floor() as i64,
+        )
+    };
+    let mut index: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, t) in trs_b.iter().enumerate() {
+        let (lo, hi) = triangle_bbox(t);
+        let (c0, c1) = (cell(lo[0], lo[1]), cell(hi[0], hi[1]));
+        for cx in c0.0..=c1.0 {
+            for cy in c0.1..=c1.1 {
+                index.entry((cx, cy)).or_default().push(i);
+            }
+        }
+    }
+    let mut re: Vec<Vec<[f64; 3]>> = Vec::new();
+    let mut candidates: Vec<usize> = Vec::new();
+    //-- only the cells in the bbox of `after`
+    let cmax = cell(bbox[2], bbox[3]);
+    for ta in &trs_a {
+        let (lo, hi) = triangle_bbox(ta);
+        let (c0, c1) = (cell(lo[0], lo[1]), cell(hi[0], hi[1]));
+        candidates.clear();
+        for cx in c0.0.max(0)..=c1.0.min(cmax.0) {
+            for cy in c0.1.max(0)..=c1.1.min(cmax.1) {
+                if let Some(trs) = index.get(&(cx, cy)) {
+                    candidates.extend(trs.iter());
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        for i in &candidates {
+            let tb = &trs_b[*i];
+            let mut piece: Vec<[f64; 3]> = ta.to_vec();
+            for j in 0..3 {
+                piece = clip_half_plane(&piece, &tb[j], &tb[(j + 1) % 3]);
+                if piece.is_empty() {
+                    break;
+                }
+            }
+            if piece.len() < 3 {
+                continue;
+            }
+            for p in piece.iter_mut() {
+                p[2] = geom::z_on_plane(&tb[0], &tb[1], &tb[2], p)
+                    - geom::z_on_plane(&ta[0], &ta[1], &ta[2], p);
+            }
+            re.push(piece);
+        }
+    }
+    Ok(re)
+}
+
+/// The finite triangles (CCW) with the coordinates of their vertices
+fn triangles(dt: &Triangulation) -> Vec<[[f64; 3]; 3]> {
+    dt.all_finite_triangles()
+        .iter()
+        .map(|tr| {
+            [
+                dt.stars[tr.v[0]].pt,
+                dt.stars[tr.v[1]].pt,
+                dt.stars[tr.v[2]].pt,
+            ]
+        })
+        .collect()
+}
+
+fn triangle_bbox(t: &[[f64; 3]; 3]) -> ([f64; 2], [f64; 2]) {
+    let mut lo = [t[0][0], t[0][1]];
+    let mut hi = lo;
+    for p in &t[1..] {
+        lo = [lo[0].min(p[0]), lo[1].min(p[1])];
+        hi = [hi[0].max(p[0]), hi[1].max(p[1])];
+    }
+    (lo, hi)
+}
+
+/// Clips the convex polygon with the half-plane on the left of `a`->`b` (Sutherland–Hodgman)
+fn clip_half_plane(poly: &[[f64; 3]], a: &[f64; 3], b: &[f64; 3]) -> Vec<[f64; 3]> {
+    let side = |p: &[f64; 3]| (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0]);
+    clip(poly, side)
+}
+
+/// Clips the polygon to the part where `sign` * z >= 0 (z is linear in the polygon)
+fn clip_positive(poly: &[[f64; 3]], sign: f64) -> Vec<[f64; 3]> {
+    clip(poly, |p: &[f64; 3]| sign * p[2])
+}
+
+/// Clips the convex polygon to the part where the linear function `f` is >= 0, the z-values
+/// of the new points are interpolated
+fn clip(poly: &[[f64; 3]], f: impl Fn(&[f64; 3]) -> f64) -> Vec<[f64; 3]> {
+    let mut re: Vec<[f64; 3]> = Vec::new();
+    for i in 0..poly.len() {
+        let (p, q) = (&poly[i], &poly[(i + 1) % poly.len()]);
+        let (fp, fq) = (f(p), f(q));
+        if fp >= 0.0 {
+            re.push(*p);
+        }
+        if (fp > 0.0 && fq < 0.0) || (fp < 0.0 && fq > 0.0) {
+            let t = fp / (fp - fq);
+            re.push([
+                p[0] + t * (q[0] - p[0]),
+                p[1] + t * (q[1] - p[1]),
+                p[2] + t * (q[2] - p[2]),
+            ]);
+        }
+    }
+    if re.len() < 3 {
+        re.clear();
+    }
+    re
+}
+
+fn polygon_area(poly: &[[f64; 3]]) -> f64 {
+    (1..poly.len().saturating_sub(1))
+        .map(|i| geom::area2d_triangle(&poly[0], &poly[i], &poly[i + 1]))
+        .sum()
+}
+
+/// The volume between the polygon and z=0 (negative below), the z-values are linear
+fn polygon_volume(poly: &[[f64; 3]]) -> f64 {
+    (1..poly.len().saturating_sub(1))
+        .map(|i| {
+            geom::area2d_triangle(&poly[0], &poly[i], &poly[i + 1])
+                * (poly[0][2] + poly[i][2] + poly[i + 1][2])
+                / 3.0
+        })
+        .sum()
+}
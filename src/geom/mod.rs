@@ -7,6 +7,8 @@
 
 extern crate robust;
 
+use crate::Triangulation;
+
 pub fn det3x3t(a: &[f64], b: &[f64], c: &[f64]) -> f64 {
     ((a[0] - c[0]) * (b[1] - c[1])) - ((a[1] - c[1]) * (b[0] - c[0]))
 }
@@ -223,3 +225,113 @@ pub fn bbox2d(pts: &Vec<[f64; 3]>) -> [f64; 4] {
     }
     re
 }
+
+/// Solves the linear system `a x = b` (Gaussian elimination with partial pivoting),
+/// `None` is returned if the matrix is singular
+pub fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    let scale = a.iter().flatten().fold(0.0_f64, |m, v| m.max(v.abs()));
+    for k in 0..n {
+        //-- pivot
+        let mut imax = k;
+        for i in (k + 1)..n {
+            if a[i][k].abs() > a[imax][k].abs() {
+                imax = i;
+            }
+        }
+        if a[imax][k].abs() <= 1e-12 * scale {
+            return None;
+        }
+        a.swap(k, imax);
+        b.swap(k, imax);
+        let (upper, lower) = a.split_at_mut(k + 1);
+        let pivot = &upper[k];
+        for (i, row) in lower.iter_mut().enumerate() {
+            let f = row[k] / pivot[k];
+            for (x, p) in row.iter_mut().zip(pivot.iter()).skip(k) {
+                *x -= f * p;
+            }
+            b[k + 1 + i] -= f * b[k];
+        }
+    }
+    let mut x: Vec<f64> = vec![0.0; n];
+    for i in (0..n).rev() {
+        let mut s = b[i];
+        for j in (i + 1)..n {
+            s -= a[i][j] * x[j];
+        }
+        x[i] = s / a[i][i];
+    }
+    Some(x)
+}
+
+/// The z-value at `p` (only its x and y are used) of the plane through the 3 points,
+/// `a`-`b`-`c` must not be collinear in 2D
+pub(crate) fn z_on_plane(a: &[f64], b: &[f64], c: &[f64], p: &[f64]) -> f64 {
+    (area2d_triangle(p, b, c) * a[2]
+        + area2d_triangle(a, p, c) * b[2]
+        + area2d_triangle(a, b, p) * c[2])
+        / area2d_triangle(a, b, c)
+}
+
+/// The finite triangles of the triangulation, each once (CCW and with the smallest vertex first)
+pub(crate) fn finite_triangles(dt: &Triangulation) -> Vec<[usize; 3]> {
+    let mut trs: Vec<[usize; 3]> = Vec::new();
+    for vi in 1..dt.stars.len() {
+        let l = &dt.stars[vi].link;
+        for (i, v) in l.iter().enumerate() {
+            let w = l[l.next_index(i)];
+            if *v > vi && w > vi {
+                trs.push([vi, *v, w]);
+            }
+        }
+    }
+    trs
+}
+
+/// A closed ring of a polygon (the first point is repeated at the end), CCW for an
+/// exterior ring and CW for a hole
+pub(crate) type Ring = Vec<[f64; 3]>;
+
+/// Groups the rings into polygons: each hole (CW ring) goes to the smallest exterior
+/// ring (CCW) that contains it
+pub(crate) fn assemble_polygons(rings: Vec<Ring>) -> Vec<Vec<Ring>> {
+    let (exteriors, holes): (Vec<Ring>, Vec<Ring>) =
+        rings.into_iter().partition(|r| signed_area(r) > 0.0);
+    let areas: Vec<f64> = exteriors.iter().map(|r| signed_area(r)).collect();
+    let mut polygons: Vec<Vec<Ring>> = exteriors.into_iter().map(|r| vec![r]).collect();
+    for h in holes {
+        //-- the middle of its first edge is inside the exterior ring
+        let q = [(h[0][0] + h[1][0]) / 2.0, (h[0][1] + h[1][1]) / 2.0];
+        let owner = (0..polygons.len())
+            .filter(|i| point_in_ring(&q, &polygons[*i][0]))
+            .min_by(|i, j| areas[*i].total_cmp(&areas[*j]));
+        if let Some(i) = owner {
+            polygons[i].push(h);
+        }
+    }
+    polygons
+}
+
+/// Signed area of a closed ring (positive if CCW)
+fn signed_area(ring: &[[f64; 3]]) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1])
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Whether `p` is inside the closed ring (crossing number)
+fn point_in_ring(p: &[f64; 2], ring: &[[f64; 3]]) -> bool {
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let (a, b) = (&w[0], &w[1]);
+        if (a[1] > p[1]) != (b[1] > p[1]) {
+            let x = a[0] + (p[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0]);
+            if p[0] < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
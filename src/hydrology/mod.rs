@@ -0,0 +1,518 @@
+//! # hydrology
+//!
+//! Flow routing directly on the TIN: the steepest descent directions of the triangles and of
+//! the vertices, the flow accumulation (the contributing area of each vertex), the
+//! drainage lines (the channels), the pits and their catchments, and the removal of the
+//! depressions (by filling or by breaching).
+//!
+//! The flow is routed from vertex to vertex: each vertex drains to its *receiver*, the adjacent
+//! vertex in the direction of the steepest descent (the single-flow-direction model, as D8 on
+//! a raster). A vertex without a lower adjacent vertex is a pit (or an outlet if it is on the
+//! convex hull).
+
+use crate::terrain::triangle_gradient;
+use crate::StartinError;
+use crate::Triangle;
+use crate::Triangulation;
+
+use crate::geom;
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// The area that each vertex contributes to the flow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellArea {
+    /// the area of its Voronoi cell; the cells of the vertices on the convex hull are
+    /// unbounded and the area of `Triangles` is used for them instead
+    Voronoi,
+    /// a third of the area of its incident triangles (the sum for all the vertices is the
+    /// area of the convex hull)
+    Triangles,
+}
+
+/// A drainage line (a channel), from its upstream end to its downstream end
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrainageLine {
+    pub vertices: Vec<usize>,
+    pub points: Vec<[f64; 3]>,
+    /// the flow accumulation at the downstream end
+    pub accumulation: f64,
+}
+
+/// The catchment of an outlet: all the vertices draining to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catchment {
+    pub outlet: usize,
+    pub vertices: Vec<usize>,
+    /// the boundary of the union of the cells of the vertices (see [`CellArea::Triangles`]:
+    /// each triangle is split between its 3 vertices by joining its centroid to the middle of
+    /// its edges), as polygons with holes (the exterior rings are CCW and the holes CW, the
+    /// rings are closed)
+    pub polygons: Vec<Vec<Vec<[f64; 3]>>>,
+    /// the area (in 2D) of the polygons
+    pub area: f64,
+}
+
+/// The result of the removal of the depressions, see [`fill_depressions()`] and
+/// [`breach_depressions()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepressionRemoval {
+    /// the vertices whose z-value was modified
+    pub modified: Vec<usize>,
+    /// the volume added (filling) or removed (breaching) under the TIN
+    pub volume: f64,
+}
+
+/// The direction (a unit vector in 2D) of the steepest descent in the triangle `tr`,
+/// `None` if the triangle is flat.
+pub fn steepest_descent_triangle(
+    dt: &Triangulation,
+    tr: &Triangle,
+) -> Result<Option<[f64; 2]>, StartinError> {
+    let g = triangle_gradient(dt, tr)?;
+    let norm = (g[0] * g[0] + g[1] * g[1]).sqrt();
+    if norm == 0.0 {
+        return Ok(None);
+    }
+    Ok(Some([-g[0] / norm, -g[1] / norm]))
+}
+
+/// The receiver of the vertex `vi`: its adjacent vertex with the steepest descent (the largest
+/// drop divided by the distance in 2D), `None` if no adjacent vertex is lower (a pit).
+pub fn steepest_descent_vertex(
+    dt: &Triangulation,
+    vi: usize,
+) -> Result<Option<usize>, StartinError> {
+    if vi == 0 {
+        return Err(StartinError::VertexInfinite);
+    }
+    let p = &dt.stars[vi].pt;
+    let mut re: Option<usize> = None;
+    let mut steepest = 0.0;
+    for n in dt.adjacent_vertices_to_vertex(vi)? {
+        if n == 0 {
+            continue;
+        }
+        let q = &dt.stars[n].pt;
+        let s = (p[2] - q[2]) / geom::distance2d(p, q);
+        if s > steepest {
+            steepest = s;
+            re = Some(n);
+        }
+    }
+    Ok(re)
+}
+
+/// The receivers of all the vertices (see [`steepest_descent_vertex()`]), indexed by the
+/// vertex id (the infinite vertex and the removed vertices have none).
+pub fn flow_receivers(dt: &Triangulation) -> Result<Vec<Option<usize>>, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let mut re: Vec<Option<usize>> = vec![None; dt.stars.len()];
+    for (vi, r) in re.iter_mut().enumerate().skip(1) {
+        if !dt.stars[vi].is_deleted() {
+            *r = steepest_descent_vertex(dt, vi)?;
+        }
+    }
+    Ok(re)
+}
+
+/// The flow accumulation of each vertex (indexed by the vertex id): its own area plus the
+/// areas of all the vertices draining to it (see [`flow_receivers()`]).
+pub fn flow_accumulation(dt: &Triangulation, area: CellArea) -> Result<Vec<f64>, StartinError> {
+    let receivers = flow_receivers(dt)?;
+    let mut acc: Vec<f64> = (0..dt.stars.len())
+        .map(|vi| cell_area(dt, vi, area))
+        .collect();
+    //-- the receivers are lower, so the vertices from the highest to the lowest
+    for vi in vertices_by_decreasing_z(dt) {
+        if let Some(r) = receivers[vi] {
+            acc[r] += acc[vi];
+        }
+    }
+    Ok(acc)
+}
+
+/// The drainage lines: the vertices with a flow accumulation of at least `threshold` are
+/// connected to their receivers (`accumulation` is that returned by [`flow_accumulation()`]).
+/// The lines start at the channel heads and at the confluences, and end at the confluences
+/// and at the outlets (pits or vertices on the convex hull).
+/// Returns [`StartinError::WrongAttribute`] if `accumulation` does not have one value per
+/// vertex (indexed by the vertex id).
+pub fn drainage_lines(
+    dt: &Triangulation,
+    accumulation: &[f64],
+    threshold: f64,
+) -> Result<Vec<DrainageLine>, StartinError> {
+    if accumulation.len() != dt.stars.len() {
+        return Err(StartinError::WrongAttribute);
+    }
+    let receivers = flow_receivers(dt)?;
+    let channel = |vi: usize| vi != 0 && accumulation[vi] >= threshold;
+    let mut donors: Vec<usize> = vec![0; receivers.len()];
+    for (vi, r) in receivers.iter().enumerate() {
+        if let Some(r) = r {
+            if channel(vi) {
+                donors[*r] += 1;
+            }
+        }
+    }
+    let mut re: Vec<DrainageLine> = Vec::new();
+    for vi in vertices_by_decreasing_z(dt) {
+        if !channel(vi) || donors[vi] == 1 || receivers[vi].is_none() {
+            continue;
+        }
+        let mut vs = vec![vi];
+        let mut cur = vi;
+        while let Some(r) = receivers[cur] {
+            vs.push(r);
+            cur = r;
+            if donors[r] > 1 {
+                break;
+            }
+        }
+        re.push(DrainageLine {
+            points: vs.iter().map(|v| dt.stars[*v].pt).collect(),
+            accumulation: accumulation[cur],
+            vertices: vs,
+        });
+    }
+    Ok(re)
+}
+
+/// The pits: the vertices that have no lower adjacent vertex and that are not on the convex
+/// hull (there the flow leaves the TIN).
+pub fn pits(dt: &Triangulation) -> Result<Vec<usize>, StartinError> {
+    Ok(pits_from_receivers(dt, &flow_receivers(dt)?))
+}
+
+fn pits_from_receivers(dt: &Triangulation, receivers: &[Option<usize>]) -> Vec<usize> {
+    (1..receivers.len())
+        .filter(|vi| {
+            receivers[*vi].is_none()
+                && !dt.stars[*vi].is_deleted()
+                && !dt.is_vertex_convex_hull(*vi)
+        })
+        .collect()
+}
+
+/// The catchment of the vertex `outlet`: the vertices for which the flow goes through it.
+/// Returns [`StartinError::VertexInfinite`], [`StartinError::VertexUnknown`] or
+/// [`StartinError::VertexRemoved`] if `outlet` is not a valid vertex.
+pub fn catchment(dt: &Triangulation, outlet: usize) -> Result<Catchment, StartinError> {
+    if outlet == 0 {
+        return Err(StartinError::VertexInfinite);
+    }
+    if dt.is_vertex_removed(outlet)? {
+        return Err(StartinError::VertexRemoved);
+    }
+    let receivers = flow_receivers(dt)?;
+    let mut donors: Vec<Vec<usize>> = vec![Vec::new(); receivers.len()];
+    for (vi, r) in receivers.iter().enumerate() {
+        if let Some(r) = r {
+            donors[*r].push(vi);
+        }
+    }
+    let mut labels: Vec<Option<usize>> = vec![None; receivers.len()];
+    let mut stack = vec![outlet];
+    while let Some(vi) = stack.pop() {
+        labels[vi] = Some(0);
+        stack.extend(donors[vi].iter());
+    }
+    Ok(catchments_from_labels(dt, &labels, &[outlet]).remove(0))
+}
+
+/// The catchment of the vertex closest to (`x`, `y`), see [`catchment()`].
+pub fn catchment_at_point(dt: &Triangulation, x: f64, y: f64) -> Result<Catchment, StartinError> {
+    let vi = dt.closest_point_from(x, y, None)?;
+    catchment(dt, vi)
+}
+
+/// The catchments of all the pits (see [`pits()`]), in the same order.
+pub fn pit_catchments(dt: &Triangulation) -> Result<Vec<Catchment>, StartinError> {
+    let receivers = flow_receivers(dt)?;
+    let ps = pits_from_receivers(dt, &receivers);
+    let mut labels: Vec<Option<usize>> = vec![None; receivers.len()];
+    for (i, p) in ps.iter().enumerate() {
+        labels[*p] = Some(i);
+    }
+    //-- the receivers are lower, so from the lowest to the highest
+    for vi in vertices_by_decreasing_z(dt).into_iter().rev() {
+        if let Some(r) = receivers[vi] {
+            labels[vi] = labels[r];
+        }
+    }
+    Ok(catchments_from_labels(dt, &labels, &ps))
+}
+
+/// Fills the depressions with the priority-flood algorithm: from the vertices on the convex
+/// hull (the outlets), the vertices are visited from the lowest, and a vertex lower than the
+/// one from which it is reached is raised to its z-value.
+/// With `epsilon` > 0.0 the filled areas are not flat, they get a small gradient (`epsilon` is
+/// the minimum slope towards the outlet), so that there are no pits afterwards.
+pub fn fill_depressions(
+    dt: &mut Triangulation,
+    epsilon: f64,
+) -> Result<DepressionRemoval, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let old: Vec<f64> = dt.stars.iter().map(|s| s.pt[2]).collect();
+    let (mut heap, mut visited) = priority_flood_init(dt);
+    while let Some(HeapItem { vi, .. }) = heap.pop() {
+        let ns: Vec<usize> = dt.stars[vi]
+            .link
+            .iter()
+            .copied()
+            .filter(|n| *n != 0)
+            .collect();
+        for n in ns {
+            if visited[n] {
+                continue;
+            }
+            visited[n] = true;
+            let z =
+                dt.stars[vi].pt[2] + epsilon * geom::distance2d(&dt.stars[vi].pt, &dt.stars[n].pt);
+            if dt.stars[n].pt[2] < z {
+                dt.stars[n].pt[2] = z;
+            }
+            heap.push(HeapItem {
+                z: dt.stars[n].pt[2],
+                vi: n,
+            });
+        }
+    }
+    Ok(depression_removal(dt, &old))
+}
+
+/// Breaches the depressions: the vertices are visited as in [`fill_depressions()`], but when
+/// a vertex is lower than the one from which it is reached, the path from it to the outlet is
+/// lowered instead (only where it is higher). `epsilon` is the minimum slope of the
+/// lowered paths (with 0.0 they can be flat).
+pub fn breach_depressions(
+    dt: &mut Triangulation,
+    epsilon: f64,
+) -> Result<DepressionRemoval, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let old: Vec<f64> = dt.stars.iter().map(|s| s.pt[2]).collect();
+    let (mut heap, mut visited) = priority_flood_init(dt);
+    //-- the vertex from which each vertex was reached, towards the outlet
+    let mut parents: Vec<Option<usize>> = vec![None; dt.stars.len()];
+    while let Some(HeapItem { vi, .. }) = heap.pop() {
+        let ns: Vec<usize> = dt.stars[vi]
+            .link
+            .iter()
+            .copied()
+            .filter(|n| *n != 0)
+            .collect();
+        for n in ns {
+            if visited[n] {
+                continue;
+            }
+            visited[n] = true;
+            parents[n] = Some(vi);
+            //-- lower the path until a vertex is low enough
+            let mut prev = n;
+            let mut cur = Some(vi);
+            let mut z = dt.stars[n].pt[2];
+            while let Some(c) = cur {
+                z -= epsilon * geom::distance2d(&dt.stars[prev].pt, &dt.stars[c].pt);
+                if dt.stars[c].pt[2] <= z {
+                    break;
+                }
+                dt.stars[c].pt[2] = z;
+                prev = c;
+                cur = parents[c];
+            }
+            heap.push(HeapItem {
+                z: dt.stars[n].pt[2],
+                vi: n,
+            });
+        }
+    }
+    Ok(depression_removal(dt, &old))
+}
+
+/// An item of the priority queue of the priority-flood, the lowest vertex first
+struct HeapItem {
+    z: f64,
+    vi: usize,
+}
+
+impl PartialEq for HeapItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapItem {}
+
+impl PartialOrd for HeapItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        //-- reversed for a min-heap, the ids make the order deterministic
+        other
+            .z
+            .total_cmp(&self.z)
+            .then_with(|| other.vi.cmp(&self.vi))
+    }
+}
+
+/// The priority queue with the vertices on the convex hull, and the visited vertices
+fn priority_flood_init(dt: &Triangulation) -> (BinaryHeap<HeapItem>, Vec<bool>) {
+    let mut visited: Vec<bool> = vec![false; dt.stars.len()];
+    let mut heap: BinaryHeap<HeapItem> = BinaryHeap::new();
+    for vi in dt.stars[0].link.iter() {
+        visited[*vi] = true;
+        heap.push(HeapItem {
+            z: dt.stars[*vi].pt[2],
+            vi: *vi,
+        });
+    }
+    (heap, visited)
+}
+
+/// The vertices modified and the volume between the old z-values and the new ones
+fn depression_removal(dt: &Triangulation, old: &[f64]) -> DepressionRemoval {
+    let mut modified: Vec<usize> = Vec::new();
+    let mut volume = 0.0;
+    for (vi, z) in old.iter().enumerate().skip(1) {
+        let dz = dt.stars[vi].pt[2] - z;
+        if !dt.stars[vi].is_deleted() && dz != 0.0 {
+            modified.push(vi);
+            volume += dz.abs() * cell_area(dt, vi, CellArea::Triangles);
+        }
+    }
+    DepressionRemoval { modified, volume }
+}
+
+/// A node of the boundaries of the cells: a vertex, the middle of an edge, or the centroid of
+/// a triangle (as returned by `geom::finite_triangles()`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum CellNode {
+    Vertex(usize),
+    Edge(usize, usize),
+    Centroid([usize; 3]),
+}
+
+fn edge_node(a: usize, b: usize) -> CellNode {
+    CellNode::Edge(a.min(b), a.max(b))
+}
+
+fn cell_node_point(dt: &Triangulation, n: &CellNode) -> [f64; 3] {
+    let avg = |vs: &[usize]| {
+        let mut p = [0.0; 3];
+        for v in vs {
+            for (c, x) in p.iter_mut().zip(dt.stars[*v].pt) {
+                *c += x / vs.len() as f64;
+            }
+        }
+        p
+    };
+    match n {
+        CellNode::Vertex(v) => dt.stars[*v].pt,
+        CellNode::Edge(a, b) => avg(&[*a, *b]),
+        CellNode::Centroid(t) => avg(t),
+    }
+}
+
+/// Builds the catchments, `labels` is the index in `outlets` of the catchment of each vertex.
+/// The part of the cell of a vertex in a triangle is a quadrilateral (CCW), its edges are
+/// added with signed counts so that those shared by 2 cells of the same catchment cancel.
+fn catchments_from_labels(
+    dt: &Triangulation,
+    labels: &[Option<usize>],
+    outlets: &[usize],
+) -> Vec<Catchment> {
+    let mut counts: Vec<HashMap<(CellNode, CellNode), i32>> = vec![HashMap::new(); outlets.len()];
+    for t in geom::finite_triangles(dt) {
+        for i in 0..3 {
+            let (v, n, p) = (t[i], t[(i + 1) % 3], t[(i + 2) % 3]);
+            if let Some(l) = labels[v] {
+                let quad = [
+                    CellNode::Vertex(v),
+                    edge_node(v, n),
+                    CellNode::Centroid(t),
+                    edge_node(p, v),
+                ];
+                for j in 0..4 {
+                    let (a, b) = (quad[j], quad[(j + 1) % 4]);
+                    *counts[l].entry((a, b)).or_insert(0) += 1;
+                    *counts[l].entry((b, a)).or_insert(0) -= 1;
+                }
+            }
+        }
+    }
+    let mut vertices: Vec<Vec<usize>> = vec![Vec::new(); outlets.len()];
+    for (vi, l) in labels.iter().enumerate() {
+        if let Some(l) = l {
+            vertices[*l].push(vi);
+        }
+    }
+    let mut re: Vec<Catchment> = Vec::new();
+    for (l, (outlet, vertices)) in outlets.iter().zip(vertices).enumerate() {
+        //-- each node of the boundary has one outgoing edge
+        let mut next: HashMap<CellNode, CellNode> = counts[l]
+            .iter()
+            .filter(|(_, c)| **c > 0)
+            .map(|((a, b), _)| (*a, *b))
+            .collect();
+        let mut rings: Vec<geom::Ring> = Vec::new();
+        while let Some(start) = next.keys().next().copied() {
+            let mut ring: geom::Ring = vec![cell_node_point(dt, &start)];
+            let mut cur = start;
+            while let Some(n) = next.remove(&cur) {
+                ring.push(cell_node_point(dt, &n));
+                cur = n;
+            }
+            rings.push(ring);
+        }
+        re.push(Catchment {
+            outlet: *outlet,
+            area: vertices
+                .iter()
+                .map(|vi| cell_area(dt, *vi, CellArea::Triangles))
+                .sum(),
+            vertices,
+            polygons: geom::assemble_polygons(rings),
+        });
+    }
+    re
+}
+
+/// The area contributed by the vertex `vi`, 0.0 for the infinite and the removed vertices
+fn cell_area(dt: &Triangulation, vi: usize, area: CellArea) -> f64 {
+    if vi == 0 || dt.stars[vi].is_deleted() {
+        return 0.0;
+    }
+    if area == CellArea::Voronoi && !dt.is_vertex_convex_hull(vi) {
+        return dt.voronoi_cell_area(vi, false).unwrap_or(0.0);
+    }
+    let l = &dt.stars[vi].link;
+    let mut a = 0.0;
+    for (i, n) in l.iter().enumerate() {
+        let m = l[l.next_index(i)];
+        if *n != 0 && m != 0 {
+            a += geom::area2d_triangle(&dt.stars[vi].pt, &dt.stars[*n].pt, &dt.stars[m].pt);
+        }
+    }
+    a / 3.0
+}
+
+/// The finite vertices sorted from the highest to the lowest
+pub(crate) fn vertices_by_decreasing_z(dt: &Triangulation) -> Vec<usize> {
+    let mut vs: Vec<usize> = (1..dt.stars.len())
+        .filter(|vi| !dt.stars[*vi].is_deleted())
+        .collect();
+    vs.sort_by(|a, b| dt.stars[*b].pt[2].total_cmp(&dt.stars[*a].pt[2]));
+    vs
+}
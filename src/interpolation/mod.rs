@@ -1,78 +1,756 @@
+use crate::attributes::AttributeColumn;
+use crate::triangle_key;
+use crate::Link;
 use crate::StartinError;
 use crate::Triangulation;
 use kdbush::KDBush;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 
 use crate::geom;
 
 pub trait Interpolant {
     fn interpolate(
         &self,
-        dt: &mut Triangulation,
+        dt: &Triangulation,
         locations: &Vec<[f64; 2]>,
     ) -> Vec<Result<f64, StartinError>>;
+
+    /// Interpolates at each location the values of all the `targets` (the z-coordinate
+    /// and/or numeric vertex attributes).
+    /// The result for each location has one value per target (in the same order).
+    ///
+    /// The default implementation is not done in one pass: it calls
+    /// [`Interpolant::interpolate()`] once per target, and for each attribute it copies the
+    /// triangulation (with the z-values replaced by those of the attribute).
+    /// The interpolants of startin override it and compute the neighbours and the weights
+    /// only once per location.
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locations: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let rts = match resolve_targets(dt, targets) {
+            Ok(x) => x,
+            Err(why) => return locations.iter().map(|_| Err(why.clone())).collect(),
+        };
+        let mut re: Vec<Result<Vec<f64>, StartinError>> =
+            locations.iter().map(|_| Ok(Vec::new())).collect();
+        for rt in &rts {
+            let zs = match rt {
+                ResolvedTarget::Z => self.interpolate(dt, locations),
+                ResolvedTarget::Column(_) => {
+                    let mut copy = dt.copy_geometry();
+                    for (vi, s) in copy.stars.iter_mut().enumerate().skip(1) {
+                        if !s.is_deleted() {
+                            s.pt[2] = target_value(dt, rt, vi);
+                        }
+                    }
+                    self.interpolate(&copy, locations)
+                }
+            };
+            for (r, z) in re.iter_mut().zip(zs) {
+                if let Ok(values) = r {
+                    match z {
+                        Ok(z) => values.push(z),
+                        Err(why) => *r = Err(why),
+                    }
+                }
+            }
+        }
+        re
+    }
 }
 
 pub fn interpolate(
     interpolant: &impl Interpolant,
-    dt: &mut Triangulation,
+    dt: &Triangulation,
     locs: &Vec<[f64; 2]>,
 ) -> Vec<Result<f64, StartinError>> {
     interpolant.interpolate(dt, locs)
 }
 
+/// Interpolates several values per location (see [`Interpolant::interpolate_targets()`])
+pub fn interpolate_targets(
+    interpolant: &impl Interpolant,
+    dt: &Triangulation,
+    locs: &Vec<[f64; 2]>,
+    targets: &[Target],
+) -> Vec<Result<Vec<f64>, StartinError>> {
+    interpolant.interpolate_targets(dt, locs, targets)
+}
+
+/// Interpolants whose estimation is a weighted average of the values at some vertices
+/// of the triangulation.
+/// The weights can be used to interpolate other values, or to propagate uncertainties.
+pub trait WeightedInterpolant: Interpolant {
+    /// Returns for each location the list of (vertex ID, weight) pairs used for the
+    /// interpolation, the weights sum to 1.0.
+    /// If the location is on a vertex, then only that vertex is returned.
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locations: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>>;
+}
+
+/// Returns the interpolation weights of each location (see [`WeightedInterpolant::weights()`])
+pub fn interpolation_weights(
+    interpolant: &impl WeightedInterpolant,
+    dt: &Triangulation,
+    locs: &Vec<[f64; 2]>,
+) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+    interpolant.weights(dt, locs)
+}
+
+/// Returns the natural neighbours of each location, that is the vertices
+/// whose Voronoi cell would be modified if the location was inserted
+/// in the triangulation (ordered CCW).
+/// If the location is on a vertex, then only that vertex is returned.
+pub fn natural_neighbours(
+    dt: &Triangulation,
+    locs: &Vec<[f64; 2]>,
+) -> Vec<Result<Vec<usize>, StartinError>> {
+    let mut start: Option<usize> = None;
+    locs.iter()
+        .map(|p| match natural_neighbours_of(dt, p, &mut start)? {
+            NaturalNeighbours::Vertex(vi) => Ok(vec![vi]),
+            NaturalNeighbours::Cavity(l) => Ok(l.0),
+        })
+        .collect()
+}
+
+/// A regular grid (a raster) whose cells are squares of size `cellsize`,
+/// `origin` is the lower-left corner of the grid.
+/// The cells are ordered row by row, starting with the top row (as in most raster formats).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub origin: [f64; 2],
+    pub cellsize: f64,
+    pub ncols: usize,
+    pub nrows: usize,
+}
+
+impl Grid {
+    /// The grid covering the bbox `[minx, miny, maxx, maxy]` (eg from [`Triangulation::get_bbox()`])
+    pub fn from_bbox(bbox: &[f64], cellsize: f64) -> Grid {
+        Grid {
+            origin: [bbox[0], bbox[1]],
+            cellsize,
+            ncols: (((bbox[2] - bbox[0]) / cellsize).ceil() as usize).max(1),
+            nrows: (((bbox[3] - bbox[1]) / cellsize).ceil() as usize).max(1),
+        }
+    }
+
+    /// The centres of the cells (the locations to interpolate)
+    pub fn cell_centres(&self) -> Vec<[f64; 2]> {
+        let mut re: Vec<[f64; 2]> = Vec::with_capacity(self.nrows * self.ncols);
+        for row in (0..self.nrows).rev() {
+            for col in 0..self.ncols {
+                re.push([
+                    self.origin[0] + (col as f64 + 0.5) * self.cellsize,
+                    self.origin[1] + (row as f64 + 0.5) * self.cellsize,
+                ]);
+            }
+        }
+        re
+    }
+
+    /// Writes the values of the cells (in the order of [`Grid::cell_centres()`]) to an
+    /// ESRI ASCII grid file, the cells that could not be interpolated get the value `nodata`
+    pub fn write_asc(
+        &self,
+        path: String,
+        values: &[Result<f64, StartinError>],
+        nodata: f64,
+    ) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(path)?);
+        writeln!(f, "ncols {}", self.ncols)?;
+        writeln!(f, "nrows {}", self.nrows)?;
+        writeln!(f, "xllcorner {}", self.origin[0])?;
+        writeln!(f, "yllcorner {}", self.origin[1])?;
+        writeln!(f, "cellsize {}", self.cellsize)?;
+        writeln!(f, "NODATA_value {}", nodata)?;
+        for row in values.chunks(self.ncols) {
+            let line: Vec<String> = row
+                .iter()
+                .map(|v| match v {
+                    Ok(z) if !z.is_nan() => z.to_string(),
+                    _ => nodata.to_string(),
+                })
+                .collect();
+            writeln!(f, "{}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Interpolates at the centres of the cells of the grid
+pub fn interpolate_grid(
+    interpolant: &impl Interpolant,
+    dt: &Triangulation,
+    grid: &Grid,
+) -> Vec<Result<f64, StartinError>> {
+    interpolant.interpolate(dt, &grid.cell_centres())
+}
+
+/// Same as [`interpolate()`] but the locations are processed in parallel
+/// (requires the feature `rayon`).
+/// The locations are sorted along a Morton curve and split into chunks, so that
+/// the locations of one chunk are close to each other and the walks are short;
+/// the results are returned in the same order as `locs`.
+#[cfg(feature = "rayon")]
+pub fn par_interpolate(
+    interpolant: &(impl Interpolant + Sync),
+    dt: &Triangulation,
+    locs: &Vec<[f64; 2]>,
+) -> Vec<Result<f64, StartinError>> {
+    par_chunks(locs, |chunk| interpolant.interpolate(dt, chunk))
+}
+
+/// Same as [`interpolate_targets()`] but the locations are processed in parallel
+/// (requires the feature `rayon`, see [`par_interpolate()`])
+#[cfg(feature = "rayon")]
+pub fn par_interpolate_targets(
+    interpolant: &(impl Interpolant + Sync),
+    dt: &Triangulation,
+    locs: &Vec<[f64; 2]>,
+    targets: &[Target],
+) -> Vec<Result<Vec<f64>, StartinError>> {
+    par_chunks(locs, |chunk| {
+        interpolant.interpolate_targets(dt, chunk, targets)
+    })
+}
+
+/// Number of locations processed sequentially by one task
+#[cfg(feature = "rayon")]
+const PAR_CHUNK_SIZE: usize = 1024;
+
+/// Sorts the locations along a Morton curve, processes chunks of them in parallel
+/// with `f`, and puts back the results in the order of `locs`
+#[cfg(feature = "rayon")]
+fn par_chunks<T, F>(locs: &[[f64; 2]], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Vec<[f64; 2]>) -> Vec<T> + Sync,
+{
+    use rayon::prelude::*;
+    let mut bbox: [f64; 4] = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+    for p in locs {
+        bbox[0] = bbox[0].min(p[0]);
+        bbox[1] = bbox[1].min(p[1]);
+        bbox[2] = bbox[2].max(p[0]);
+        bbox[3] = bbox[3].max(p[1]);
+    }
+    let mut order: Vec<(u64, usize)> = locs
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (morton_code(p, &bbox), i))
+        .collect();
+    order.par_sort_unstable();
+    let mut re: Vec<(usize, T)> = order
+        .par_chunks(PAR_CHUNK_SIZE)
+        .flat_map_iter(|chunk| {
+            let chunk_locs: Vec<[f64; 2]> = chunk.iter().map(|(_, i)| locs[*i]).collect();
+            let values = f(&chunk_locs);
+            chunk.iter().map(|(_, i)| *i).zip(values)
+        })
+        .collect();
+    re.par_sort_unstable_by_key(|(i, _)| *i);
+    re.into_iter().map(|(_, v)| v).collect()
+}
+
+/// The Morton code (z-order) of `p` inside `bbox`, with 32 bits per coordinate
+#[cfg(feature = "rayon")]
+fn morton_code(p: &[f64; 2], bbox: &[f64; 4]) -> u64 {
+    fn spread(v: u64) -> u64 {
+        let mut v = v & 0xffff_ffff;
+        v = (v | (v << 16)) & 0x0000_ffff_0000_ffff;
+        v = (v | (v << 8)) & 0x00ff_00ff_00ff_00ff;
+        v = (v | (v << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+        v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+        (v | (v << 1)) & 0x5555_5555_5555_5555
+    }
+    let scale = |v: f64, min: f64, max: f64| -> u64 {
+        if max > min {
+            (((v - min) / (max - min)) * (u32::MAX as f64)) as u64
+        } else {
+            0
+        }
+    };
+    spread(scale(p[0], bbox[0], bbox[2])) | (spread(scale(p[1], bbox[1], bbox[3])) << 1)
+}
+
+/// What is interpolated: the z-coordinate of the vertices, or one of their
+/// (numeric) attributes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Z,
+    /// the name of a vertex attribute (of type "f64", "i64", "u64" or "bool"),
+    /// a vertex for which the attribute is not set has the value `f64::NAN`
+    Attribute(String),
+}
+
+/// A target that was checked against the schema of the triangulation
+enum ResolvedTarget<'a> {
+    Z,
+    Column(&'a AttributeColumn),
+}
+
+fn resolve_targets<'a>(
+    dt: &'a Triangulation,
+    targets: &[Target],
+) -> Result<Vec<ResolvedTarget<'a>>, StartinError> {
+    let mut re: Vec<ResolvedTarget> = Vec::new();
+    for t in targets {
+        match t {
+            Target::Z => re.push(ResolvedTarget::Z),
+            Target::Attribute(name) => {
+                let c = dt.attribute_column(name)?;
+                if c.as_string().is_some() {
+                    return Err(StartinError::WrongAttribute);
+                }
+                re.push(ResolvedTarget::Column(c));
+            }
+        }
+    }
+    Ok(re)
+}
+
+fn target_value(dt: &Triangulation, t: &ResolvedTarget, vi: usize) -> f64 {
+    match t {
+        ResolvedTarget::Z => dt.stars[vi].pt[2],
+        ResolvedTarget::Column(c) => c.get(vi).and_then(|v| v.as_f64()).unwrap_or(f64::NAN),
+    }
+}
+
+/// How the gradient of the values at the vertices is estimated (used by the
+/// interpolants that are C1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientEstimation {
+    /// average of the normals of the incident triangles (as [`Triangulation::normal_vertex()`])
+    Normals,
+    /// least-squares plane through the vertex and its adjacent vertices
+    /// (weighted by the inverse of the squared distance)
+    LeastSquares,
+}
+
+/// The gradient (df/dx, df/dy) at the vertex `vi` of the values of the target
+fn vertex_gradient(
+    dt: &Triangulation,
+    vi: usize,
+    t: &ResolvedTarget,
+    method: GradientEstimation,
+) -> [f64; 2] {
+    let l = &dt.stars[vi].link;
+    let x = [
+        dt.stars[vi].pt[0],
+        dt.stars[vi].pt[1],
+        target_value(dt, t, vi),
+    ];
+    if method == GradientEstimation::LeastSquares {
+        let (mut a11, mut a12, mut a22, mut b1, mut b2) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for v in l.iter().filter(|v| **v != 0) {
+            let dx = dt.stars[*v].pt[0] - x[0];
+            let dy = dt.stars[*v].pt[1] - x[1];
+            let df = target_value(dt, t, *v) - x[2];
+            let w = 1.0 / (dx * dx + dy * dy);
+            a11 += w * dx * dx;
+            a12 += w * dx * dy;
+            a22 += w * dy * dy;
+            b1 += w * dx * df;
+            b2 += w * dy * df;
+        }
+        let det = a11 * a22 - a12 * a12;
+        //-- all the neighbours collinear (only possible on the convex hull)
+        if det.abs() > 1e-12 * a11 * a22 {
+            return [(a22 * b1 - a12 * b2) / det, (a11 * b2 - a12 * b1) / det];
+        }
+    }
+    let mut n: [f64; 3] = [0.0, 0.0, 0.0];
+    for (i, v) in l.iter().enumerate() {
+        let w = l[l.next_index(i)];
+        if *v == 0 || w == 0 {
+            continue;
+        }
+        let a = [
+            dt.stars[*v].pt[0],
+            dt.stars[*v].pt[1],
+            target_value(dt, t, *v),
+        ];
+        let b = [dt.stars[w].pt[0], dt.stars[w].pt[1], target_value(dt, t, w)];
+        let tn = geom::normal_triangle(&x, &a, &b, true);
+        for j in 0..3 {
+            n[j] += tn[j];
+        }
+    }
+    [-n[0] / n[2], -n[1] / n[2]]
+}
+
+/// The weights of the vertices used for the interpolation at one location,
+/// the interpolated value is `sum(w * value) / norm`
+struct Weights {
+    ws: Vec<(usize, f64)>,
+    norm: f64,
+}
+
+impl Weights {
+    /// The location is one of the vertices (or the interpolant uses only one)
+    fn single(vi: usize) -> Weights {
+        Weights {
+            ws: vec![(vi, 1.0)],
+            norm: 1.0,
+        }
+    }
+
+    /// The weights divided by the norm, so that they sum to 1
+    fn normalised(&self) -> Vec<(usize, f64)> {
+        self.ws.iter().map(|(vi, w)| (*vi, w / self.norm)).collect()
+    }
+
+    fn value(&self, dt: &Triangulation, t: &ResolvedTarget) -> f64 {
+        let mut z: f64 = 0.0;
+        for (vi, w) in &self.ws {
+            z += w * target_value(dt, t, *vi);
+        }
+        z / self.norm
+    }
+}
+
+fn weights_to_z(
+    dt: &Triangulation,
+    ws: Vec<Result<Weights, StartinError>>,
+) -> Vec<Result<f64, StartinError>> {
+    ws.into_iter()
+        .map(|w| w.map(|w| w.value(dt, &ResolvedTarget::Z)))
+        .collect()
+}
+
+fn weights_to_targets(
+    dt: &Triangulation,
+    ws: Vec<Result<Weights, StartinError>>,
+    targets: &[Target],
+) -> Vec<Result<Vec<f64>, StartinError>> {
+    let rts = match resolve_targets(dt, targets) {
+        Ok(x) => x,
+        Err(why) => return ws.iter().map(|_| Err(why.clone())).collect(),
+    };
+    ws.into_iter()
+        .map(|w| w.map(|w| rts.iter().map(|t| w.value(dt, t)).collect()))
+        .collect()
+}
+
+/// The natural neighbours of a location
+enum NaturalNeighbours {
+    /// the location is (within the snap tolerance) on this vertex
+    Vertex(usize),
+    /// the link (CCW) the location would have if it was inserted
+    Cavity(Link),
+}
+
+/// Computes the natural neighbours of `p` without modifying the triangulation:
+/// the triangles whose circumcircle contains `p` (the Bowyer-Watson cavity) are
+/// collected, and the boundary of the cavity is the future link of `p`.
+/// `start` is the vertex where the walk starts, and is updated for the next location.
+fn natural_neighbours_of(
+    dt: &Triangulation,
+    p: &[f64; 2],
+    start: &mut Option<usize>,
+) -> Result<NaturalNeighbours, StartinError> {
+    let tr = dt.locate_from(p[0], p[1], *start)?;
+    *start = Some(tr.v[0]);
+    let q: [f64; 3] = [p[0], p[1], 0.0];
+    let snaptol2 = dt.get_snap_tolerance() * dt.get_snap_tolerance();
+    for v in &tr.v {
+        if geom::distance2d_squared(&dt.stars[*v].pt, &q) <= snaptol2 {
+            return Ok(NaturalNeighbours::Vertex(*v));
+        }
+    }
+    let mut cavity: Vec<[usize; 3]> = vec![tr.v];
+    let mut stack: Vec<[usize; 3]> = vec![tr.v];
+    //-- edges of the boundary of the cavity, a -> b is CCW around p
+    let mut boundary: Vec<(usize, usize)> = Vec::new();
+    while let Some(t) = stack.pop() {
+        for i in 0..3 {
+            let a = t[i];
+            let b = t[(i + 1) % 3];
+            let c = dt.stars[b].link.get_next_vertex(a).unwrap();
+            if c == 0 {
+                //-- p on the boundary of the convex hull: the Voronoi cell is unbounded
+                if geom::orient2d(&dt.stars[a].pt, &dt.stars[b].pt, &q, dt.robust_predicates) == 0 {
+                    return Err(StartinError::OutsideConvexHull);
+                }
+                boundary.push((a, b));
+                continue;
+            }
+            let adj = [b, a, c];
+            if cavity.iter().any(|x| is_same_triangle(x, &adj)) {
+                continue;
+            }
+            if geom::incircle(
+                &dt.stars[b].pt,
+                &dt.stars[a].pt,
+                &dt.stars[c].pt,
+                &q,
+                dt.robust_predicates,
+            ) > 0
+            {
+                cavity.push(adj);
+                stack.push(adj);
+            } else {
+                boundary.push((a, b));
+            }
+        }
+    }
+    //-- start with the smallest vertex so that the result doesn't depend on the walk
+    let first = *boundary.iter().min().unwrap();
+    let mut l: Vec<usize> = vec![first.0];
+    let mut cur = first.1;
+    while cur != l[0] {
+        l.push(cur);
+        cur = boundary.iter().find(|(a, _)| *a == cur).unwrap().1;
+    }
+    Ok(NaturalNeighbours::Cavity(Link(l)))
+}
+
+fn is_same_triangle(t0: &[usize; 3], t1: &[usize; 3]) -> bool {
+    (0..3).any(|i| t0[i] == t1[0] && t0[(i + 1) % 3] == t1[1] && t0[(i + 2) % 3] == t1[2])
+}
+
+/// The vertices of the Voronoi cell of `q` if it was inserted, the i-th one is the
+/// circumcentre of (q, l[i], l[i+1])
+fn voronoi_vertices(dt: &Triangulation, q: &[f64; 3], l: &Link) -> Vec<Vec<f64>> {
+    (0..l.len())
+        .map(|i| geom::circle_centre(q, &dt.stars[l[i]].pt, &dt.stars[l[l.next_index(i)]].pt))
+        .collect()
+}
+
+/// The spatial index (a kd-tree) of the finite vertices of a triangulation,
+/// it is cached in the triangulation
+pub(crate) struct VertexIndex {
+    index: Option<KDBush>,
+    ids: Vec<usize>,
+    /// a vertex that is ignored (the one held out by [`cross_validation()`])
+    excluded: Option<usize>,
+}
+
+impl VertexIndex {
+    pub(crate) fn new(dt: &Triangulation) -> VertexIndex {
+        let ids: Vec<usize> = (1..dt.stars.len())
+            .filter(|vi| !dt.stars[*vi].is_deleted())
+            .collect();
+        let pts: Vec<(f64, f64)> = ids
+            .iter()
+            .map(|vi| (dt.stars[*vi].pt[0], dt.stars[*vi].pt[1]))
+            .collect();
+        VertexIndex {
+            index: match pts.is_empty() {
+                true => None,
+                false => Some(KDBush::create(pts, kdbush::DEFAULT_NODE_SIZE)),
+            },
+            ids,
+            excluded: None,
+        }
+    }
+
+    /// The vertices inside the circle of centre `p` and radius `r`
+    fn within(&self, p: &[f64; 2], r: f64) -> Vec<usize> {
+        let mut ns: Vec<usize> = Vec::new();
+        if let Some(index) = &self.index {
+            index.within(p[0], p[1], r, |i| {
+                if Some(self.ids[i]) != self.excluded {
+                    ns.push(self.ids[i])
+                }
+            });
+        }
+        ns
+    }
+}
+
+/// How the vertices used by [`IDW`] are selected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IDWSearch {
+    /// all the vertices within the radius
+    Radius,
+    /// the k nearest vertices (within the radius, which can be `f64::INFINITY`)
+    KNearest(usize),
+    /// the plane is divided into `sectors` sectors around the location (4 for
+    /// quadrants, 8 for octants), and the `per_sector` nearest vertices (within the radius)
+    /// of each sector are used
+    Sectors { sectors: usize, per_sector: usize },
+}
+
 /// Estimation of z-value with interpolation: IDW
 /// (this function doesn't use the TIN at all, added here for
 /// convenience and teaching purposes)
+///
+/// The vertices are selected with `search`; if there are fewer than `min_neighbours`
+/// then [`StartinError::NotEnoughData`] is returned.
+/// It is created with [`IDW::new()`] or [`IDW::default()`], and then configured with
+/// [`IDW::with_search()`] and [`IDW::with_min_neighbours()`].
+#[non_exhaustive]
 pub struct IDW {
     pub radius: f64,
     pub power: f64,
+    pub search: IDWSearch,
+    pub min_neighbours: usize,
 }
-impl Interpolant for IDW {
-    fn interpolate(
+impl IDW {
+    /// All the vertices within `radius` are used (the search of the previous versions)
+    pub fn new(radius: f64, power: f64) -> IDW {
+        IDW {
+            radius,
+            power,
+            search: IDWSearch::Radius,
+            min_neighbours: 0,
+        }
+    }
+
+    /// Sets how the vertices are selected
+    pub fn with_search(mut self, search: IDWSearch) -> IDW {
+        self.search = search;
+        self
+    }
+
+    /// Sets the minimum number of vertices required to interpolate
+    pub fn with_min_neighbours(mut self, min_neighbours: usize) -> IDW {
+        self.min_neighbours = min_neighbours;
+        self
+    }
+
+    fn neighbours(
+        &self,
+        dt: &Triangulation,
+        p: &[f64; 2],
+        start: &mut Option<usize>,
+    ) -> Result<Vec<usize>, StartinError> {
+        match self.search {
+            IDWSearch::Radius => Ok(dt.vertex_index().within(p, self.radius)),
+            IDWSearch::KNearest(k) => {
+                let mut ns = if dt.is_init {
+                    k_nearest_vertices(dt, p, k, start, true)?
+                } else {
+                    //-- no triangles (eg all collinear), all the vertices are sorted
+                    let mut ns = dt.vertex_index().within(p, f64::INFINITY);
+                    ns.sort_by(|a, b| {
+                        geom::distance2d_squared(p, &dt.stars[*a].pt)
+                            .total_cmp(&geom::distance2d_squared(p, &dt.stars[*b].pt))
+                    });
+                    ns.truncate(k);
+                    ns
+                };
+                ns.retain(|vi| geom::distance2d(p, &dt.stars[*vi].pt) <= self.radius);
+                Ok(ns)
+            }
+            IDWSearch::Sectors {
+                sectors,
+                per_sector,
+            } => {
+                let mut bysector: Vec<Vec<(f64, usize)>> = vec![Vec::new(); sectors];
+                for vi in dt.vertex_index().within(p, self.radius) {
+                    let dx = dt.stars[vi].pt[0] - p[0];
+                    let dy = dt.stars[vi].pt[1] - p[1];
+                    let angle = dy.atan2(dx).rem_euclid(2.0 * std::f64::consts::PI);
+                    let i = ((angle / (2.0 * std::f64::consts::PI)) * sectors as f64) as usize;
+                    bysector[i.min(sectors - 1)].push((dx * dx + dy * dy, vi));
+                }
+                let mut ns: Vec<usize> = Vec::new();
+                for mut vs in bysector {
+                    vs.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    ns.extend(vs.iter().take(per_sector).map(|(_, vi)| *vi));
+                }
+                Ok(ns)
+            }
+        }
+    }
+
+    fn raw_weights(
         &self,
-        dt: &mut Triangulation,
+        dt: &Triangulation,
         locs: &Vec<[f64; 2]>,
-    ) -> Vec<Result<f64, StartinError>> {
-        //-- build a kd-tree
-        let mut allpts: Vec<(f64, f64)> = Vec::new();
-        for i in 0..dt.stars.len() {
-            allpts.push((dt.stars[i].pt[0], dt.stars[i].pt[1]));
-        }
-        let index = KDBush::create(allpts, kdbush::DEFAULT_NODE_SIZE);
-        //-- perform interpolations
-        let mut re: Vec<Result<f64, StartinError>> = Vec::new();
+    ) -> Vec<Result<Weights, StartinError>> {
+        let mut re: Vec<Result<Weights, StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
         for p in locs {
-            let mut ns: Vec<usize> = Vec::new();
-            index.within(p[0], p[1], self.radius, |id| ns.push(id));
+            let ns = match self.neighbours(dt, p, &mut start) {
+                Ok(ns) => ns,
+                Err(why) => {
+                    re.push(Err(why));
+                    continue;
+                }
+            };
             if ns.is_empty() {
                 re.push(Err(StartinError::SearchCircleEmpty));
+            } else if ns.len() < self.min_neighbours {
+                re.push(Err(StartinError::NotEnoughData));
             } else {
-                let mut weights: Vec<f64> = Vec::new();
-                let mut exisiting = false;
-                let mut value: f64 = std::f64::MAX;
+                let mut weights: Vec<(usize, f64)> = Vec::new();
+                let mut exisiting: Option<usize> = None;
                 for each in &ns {
                     let d = geom::distance2d(p, &dt.stars[*each].pt);
                     if d <= dt.get_snap_tolerance() {
-                        exisiting = true;
-                        value = dt.stars[*each].pt[2];
+                        exisiting = Some(*each);
                         break;
                     }
-                    weights.push(d.powf(-self.power));
+                    weights.push((*each, d.powf(-self.power)));
                 }
-                if exisiting {
-                    re.push(Ok(value));
-                } else {
-                    let mut z = 0_f64;
-                    for (i, w) in weights.iter().enumerate() {
-                        z += dt.stars[ns[i]].pt[2] * w;
+                match exisiting {
+                    Some(vi) => re.push(Ok(Weights::single(vi))),
+                    None => {
+                        let norm = weights.iter().map(|(_, w)| w).sum::<f64>();
+                        re.push(Ok(Weights { ws: weights, norm }));
                     }
-                    re.push(Ok(z / weights.iter().sum::<f64>()));
                 }
             }
         }
         re
     }
 }
+impl Default for IDW {
+    /// The 12 nearest vertices, with a power of 2.0
+    fn default() -> Self {
+        IDW {
+            radius: f64::INFINITY,
+            power: 2.0,
+            search: IDWSearch::KNearest(12),
+            min_neighbours: 0,
+        }
+    }
+}
+impl WeightedInterpolant for IDW {
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.normalised()))
+            .collect()
+    }
+}
+impl Interpolant for IDW {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_z(dt, ws)
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_targets(dt, ws, targets)
+    }
+}
 
 /// Estimation of z-value with interpolation: Laplace interpolation
 ///
@@ -80,81 +758,82 @@ impl Interpolant for IDW {
 /// is a variation of nni with distances instead of stolen areas, which yields a much
 /// faster implementation.
 pub struct Laplace {}
-impl Interpolant for Laplace {
-    fn interpolate(
+impl Laplace {
+    fn raw_weights(
         &self,
-        dt: &mut Triangulation,
+        dt: &Triangulation,
         locs: &Vec<[f64; 2]>,
-    ) -> Vec<Result<f64, StartinError>> {
-        let mut re: Vec<Result<f64, StartinError>> = Vec::new();
+    ) -> Vec<Result<Weights, StartinError>> {
+        let mut re: Vec<Result<Weights, StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
         for p in locs {
-            //-- cannot interpolate if no TIN
-            if !dt.is_init {
-                re.push(Err(StartinError::EmptyTriangulation));
-                continue;
-            }
-            //-- no extrapolation
-            let loc = dt.locate(p[0], p[1]);
-            match loc {
-                Ok(_tr) => {
-                    match dt.insert_one_pt_interpol(p[0], p[1]) {
-                        Ok(pi) => {
-                            //-- no extrapolation
-                            if dt.is_vertex_convex_hull(pi) {
-                                //-- interpolation point was added on boundary of CH
-                                //-- nothing to be done, Voronoi cell is unbounded
-                                let _rr = dt.remove(pi);
-                                re.push(Err(StartinError::OutsideConvexHull));
-                            } else {
-                                let l = &dt.stars[pi].link;
-                                let mut centres: Vec<Vec<f64>> = Vec::new();
-                                for (i, v) in l.iter().enumerate() {
-                                    let j = l.next_index(i);
-                                    centres.push(geom::circle_centre(
-                                        &dt.stars[pi].pt,
-                                        &dt.stars[*v].pt,
-                                        &dt.stars[l[j]].pt,
-                                    ));
-                                }
-                                let mut weights: Vec<f64> = Vec::new();
-                                for (i, v) in l.iter().enumerate() {
-                                    // fetch 2 voronoi centres
-                                    let e =
-                                        geom::distance2d(&centres[i], &centres[l.prev_index(i)]);
-                                    let w = geom::distance2d(&dt.stars[pi].pt, &dt.stars[*v].pt);
-                                    weights.push(e / w);
-                                }
-                                let mut z: f64 = 0.0;
-                                for (i, v) in l.iter().enumerate() {
-                                    z += weights[i] * dt.stars[*v].pt[2];
-                                }
-                                let sumweights: f64 = weights.iter().sum();
-                                //-- delete the interpolation location point
-                                let _rr = dt.remove(pi);
-                                re.push(Ok(z / sumweights));
-                            }
-                        }
-                        Err((pi, _updated)) => {
-                            re.push(Ok(dt.stars[pi].pt[2]));
-                        }
+            match natural_neighbours_of(dt, p, &mut start) {
+                Ok(NaturalNeighbours::Vertex(vi)) => re.push(Ok(Weights::single(vi))),
+                Ok(NaturalNeighbours::Cavity(l)) => {
+                    let q: [f64; 3] = [p[0], p[1], 0.0];
+                    let centres = voronoi_vertices(dt, &q, &l);
+                    let mut weights: Vec<(usize, f64)> = Vec::new();
+                    for (i, v) in l.iter().enumerate() {
+                        // fetch 2 voronoi centres
+                        let e = geom::distance2d(&centres[i], &centres[l.prev_index(i)]);
+                        let w = geom::distance2d(&q, &dt.stars[*v].pt);
+                        weights.push((*v, e / w));
                     }
+                    let sumweights: f64 = weights.iter().map(|(_, w)| w).sum();
+                    re.push(Ok(Weights {
+                        ws: weights,
+                        norm: sumweights,
+                    }));
                 }
-                Err(_e) => re.push(Err(StartinError::OutsideConvexHull)),
+                Err(why) => re.push(Err(why)),
             }
         }
         re
     }
 }
+impl WeightedInterpolant for Laplace {
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.normalised()))
+            .collect()
+    }
+}
+impl Interpolant for Laplace {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_z(dt, ws)
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_targets(dt, ws, targets)
+    }
+}
 
 /// Estimation of z-value with interpolation: nearest/closest neighbour
 pub struct NN {}
-impl Interpolant for NN {
-    fn interpolate(
+impl NN {
+    fn raw_weights(
         &self,
-        dt: &mut Triangulation,
+        dt: &Triangulation,
         locs: &Vec<[f64; 2]>,
-    ) -> Vec<Result<f64, StartinError>> {
-        let mut re: Vec<Result<f64, StartinError>> = Vec::new();
+    ) -> Vec<Result<Weights, StartinError>> {
+        let mut re: Vec<Result<Weights, StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
         for p in locs {
             //-- cannot interpolation if no TIN
             if !dt.is_init {
@@ -162,24 +841,60 @@ impl Interpolant for NN {
                 continue;
             }
             //-- TODO: should interpolate_nn() extrapolate?
-            match dt.closest_point(p[0], p[1]) {
-                Ok(vi) => re.push(Ok(dt.stars[vi].pt[2])),
+            match dt.closest_point_from(p[0], p[1], start) {
+                Ok(vi) => {
+                    start = Some(vi);
+                    re.push(Ok(Weights::single(vi)));
+                }
                 Err(why) => re.push(Err(why)),
             }
         }
         re
     }
 }
+impl WeightedInterpolant for NN {
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.normalised()))
+            .collect()
+    }
+}
+impl Interpolant for NN {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_z(dt, ws)
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_targets(dt, ws, targets)
+    }
+}
 
 /// Estimation of z-value with interpolation: linear in TIN
 pub struct TIN {}
-impl Interpolant for TIN {
-    fn interpolate(
+impl TIN {
+    fn raw_weights(
         &self,
-        dt: &mut Triangulation,
+        dt: &Triangulation,
         locs: &Vec<[f64; 2]>,
-    ) -> Vec<Result<f64, StartinError>> {
-        let mut re: Vec<Result<f64, StartinError>> = Vec::new();
+    ) -> Vec<Result<Weights, StartinError>> {
+        let mut re: Vec<Result<Weights, StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
         for p in locs {
             //-- cannot interpolate if no TIN
             if !dt.is_init {
@@ -187,21 +902,20 @@ impl Interpolant for TIN {
                 continue;
             }
             //-- no extrapolation
-            let loc = dt.locate(p[0], p[1]);
+            let loc = dt.locate_from(p[0], p[1], start);
             match loc {
                 Ok(tr) => {
+                    start = Some(tr.v[0]);
+                    //-- same order whatever the walk, thus the same rounding
+                    let v = triangle_key(&tr.v);
                     let q: [f64; 3] = [p[0], p[1], 0.0];
-                    let a0: f64 =
-                        geom::area2d_triangle(&q, &dt.stars[tr.v[1]].pt, &dt.stars[tr.v[2]].pt);
-                    let a1: f64 =
-                        geom::area2d_triangle(&q, &dt.stars[tr.v[2]].pt, &dt.stars[tr.v[0]].pt);
-                    let a2: f64 =
-                        geom::area2d_triangle(&q, &dt.stars[tr.v[0]].pt, &dt.stars[tr.v[1]].pt);
-                    let mut total = 0.;
-                    total += dt.stars[tr.v[0]].pt[2] * a0;
-                    total += dt.stars[tr.v[1]].pt[2] * a1;
-                    total += dt.stars[tr.v[2]].pt[2] * a2;
-                    re.push(Ok(total / (a0 + a1 + a2)));
+                    let a0: f64 = geom::area2d_triangle(&q, &dt.stars[v[1]].pt, &dt.stars[v[2]].pt);
+                    let a1: f64 = geom::area2d_triangle(&q, &dt.stars[v[2]].pt, &dt.stars[v[0]].pt);
+                    let a2: f64 = geom::area2d_triangle(&q, &dt.stars[v[0]].pt, &dt.stars[v[1]].pt);
+                    re.push(Ok(Weights {
+                        ws: vec![(v[0], a0), (v[1], a1), (v[2], a2)],
+                        norm: a0 + a1 + a2,
+                    }));
                 }
                 Err(_e) => re.push(Err(StartinError::OutsideConvexHull)),
             }
@@ -209,32 +923,150 @@ impl Interpolant for TIN {
         re
     }
 }
+impl WeightedInterpolant for TIN {
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.normalised()))
+            .collect()
+    }
+}
+impl Interpolant for TIN {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_z(dt, ws)
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_targets(dt, ws, targets)
+    }
+}
 
 /// Estimation of z-value with interpolation: natural neighbour interpolation (nni),
 /// also called Sibson's interpolation
 pub struct NNI {
+    /// not used anymore: the stolen areas are computed directly from the cavity
+    /// of the location, kept for backwards compatibility
     pub precompute: bool,
 }
-impl Interpolant for NNI {
-    fn interpolate(
+impl NNI {
+    fn raw_weights(
         &self,
-        dt: &mut Triangulation,
+        dt: &Triangulation,
         locs: &Vec<[f64; 2]>,
-    ) -> Vec<Result<f64, StartinError>> {
-        //-- store temporarily all the Voronoi cells areas
-        let mut vorareas: Vec<f64> = Vec::new();
-        if self.precompute {
-            vorareas.reserve_exact(dt.stars.len());
-            vorareas.push(0.);
-            for vi in 1..dt.stars.len() {
-                if !dt.stars[vi].is_deleted() {
-                    vorareas.push(dt.voronoi_cell_area(vi, true).unwrap());
-                } else {
-                    vorareas.push(0.);
+    ) -> Vec<Result<Weights, StartinError>> {
+        let mut re: Vec<Result<Weights, StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
+        for p in locs {
+            match natural_neighbours_of(dt, p, &mut start) {
+                Ok(NaturalNeighbours::Vertex(vi)) => re.push(Ok(Weights::single(vi))),
+                Ok(NaturalNeighbours::Cavity(l)) => {
+                    let q: [f64; 3] = [p[0], p[1], 0.0];
+                    let centres = voronoi_vertices(dt, &q, &l);
+                    let mut weights: Vec<(usize, f64)> = Vec::new();
+                    for (i, v) in l.iter().enumerate() {
+                        let prev = l.prev_index(i);
+                        let next = l.next_index(i);
+                        //-- the area stolen to v is delimited by the 2 new Voronoi
+                        //-- vertices and the circumcentres of the triangles incident to v
+                        //-- that are in the cavity (from l[next] to l[prev] CCW)
+                        let mut poly: Vec<Vec<f64>> = vec![centres[i].clone()];
+                        let mut a = l[next];
+                        for _ in 0..dt.stars[*v].link.len() {
+                            let b = dt.stars[*v].link.get_next_vertex(a).unwrap();
+                            poly.push(geom::circle_centre(
+                                &dt.stars[*v].pt,
+                                &dt.stars[a].pt,
+                                &dt.stars[b].pt,
+                            ));
+                            if b == l[prev] {
+                                break;
+                            }
+                            a = b;
+                        }
+                        poly.push(centres[prev].clone());
+                        let mut area: f64 = 0.0;
+                        for j in 1..(poly.len() - 1) {
+                            area += geom::area2d_triangle(&poly[0], &poly[j], &poly[j + 1]);
+                        }
+                        weights.push((*v, area.abs()));
+                    }
+                    let norm: f64 = weights.iter().map(|(_, w)| w).sum();
+                    re.push(Ok(Weights { ws: weights, norm }));
                 }
+                Err(why) => re.push(Err(why)),
             }
         }
-        let mut re: Vec<Result<f64, StartinError>> = Vec::new();
+        re
+    }
+}
+impl WeightedInterpolant for NNI {
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.normalised()))
+            .collect()
+    }
+}
+impl Interpolant for NNI {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_z(dt, ws)
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_targets(dt, ws, targets)
+    }
+}
+
+/// Estimation of z-value with interpolation: Clough-Tocher
+///
+/// Each triangle is split into 3 at its centroid, and a cubic polynomial is
+/// used in each of the sub-triangles. The surface is C1 (smooth) inside the
+/// convex hull, and passes through the vertices with the gradients estimated there.
+/// The implementation follows the one of scipy (the cross-boundary derivatives are
+/// affine invariant, see `CloughTocher2DInterpolator`).
+pub struct CloughTocher {
+    pub gradients: GradientEstimation,
+}
+impl CloughTocher {
+    fn interpolate_resolved(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[ResolvedTarget],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let mut gradients: HashMap<usize, Vec<[f64; 2]>> = HashMap::new();
+        let mut re: Vec<Result<Vec<f64>, StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
         for p in locs {
             //-- cannot interpolate if no TIN
             if !dt.is_init {
@@ -242,48 +1074,1054 @@ impl Interpolant for NNI {
                 continue;
             }
             //-- no extrapolation
-            let loc = dt.locate(p[0], p[1]);
-            match loc {
-                Ok(_tr) => {
-                    match dt.insert_one_pt_interpol(p[0], p[1]) {
-                        Ok(pi) => {
-                            //-- no extrapolation
-                            if dt.is_vertex_convex_hull(pi) {
-                                //-- interpolation point was added on boundary of CH
-                                //-- nothing to be done, Voronoi cell is unbounded
-                                let _rr = dt.remove(pi);
-                                re.push(Err(StartinError::OutsideConvexHull));
-                            } else {
-                                let nns = dt.adjacent_vertices_to_vertex(pi).unwrap();
-                                let mut weights: Vec<f64> = Vec::new();
-                                for nn in &nns {
-                                    let a = dt.voronoi_cell_area(*nn, true).unwrap();
-                                    weights.push(a);
-                                }
-                                let newarea = dt.voronoi_cell_area(pi, true).unwrap();
-                                let _rr = dt.remove(pi);
-                                for (i, nn) in nns.iter().enumerate() {
-                                    if self.precompute {
-                                        weights[i] = vorareas[*nn] - weights[i];
-                                    } else {
-                                        //-- TODO : is it faster to save them?!
-                                        weights[i] =
-                                            dt.voronoi_cell_area(*nn, true).unwrap() - weights[i];
-                                    }
-                                }
-                                let mut z: f64 = 0.0;
-                                for (i, nn) in nns.iter().enumerate() {
-                                    z += weights[i] * dt.stars[*nn].pt[2];
-                                }
-                                re.push(Ok(z / newarea));
-                            }
-                        }
-                        Err(e) => re.push(Ok(dt.stars[e.0].pt[2])),
+            let tr = match dt.locate_from(p[0], p[1], start) {
+                Ok(tr) => tr,
+                Err(_e) => {
+                    re.push(Err(StartinError::OutsideConvexHull));
+                    continue;
+                }
+            };
+            start = Some(tr.v[0]);
+            let v = triangle_key(&tr.v);
+            let pts: Vec<&[f64]> = v.iter().map(|vi| &dt.stars[*vi].pt[..]).collect();
+            let q: [f64; 3] = [p[0], p[1], 0.0];
+            let b = barycentric(&pts, &q);
+            //-- direction of the cross-boundary derivative of each edge (opposite to v[k])
+            let mut g: [f64; 3] = [-0.5, -0.5, -0.5];
+            for k in 0..3 {
+                let v1 = v[(k + 1) % 3];
+                let v2 = v[(k + 2) % 3];
+                let opp = dt.stars[v2].link.get_next_vertex(v1).unwrap();
+                if opp == 0 {
+                    continue;
+                }
+                let mut centroid: [f64; 3] = [0.0, 0.0, 0.0];
+                for vi in [v1, v2, opp] {
+                    centroid[0] += dt.stars[vi].pt[0] / 3.0;
+                    centroid[1] += dt.stars[vi].pt[1] / 3.0;
+                }
+                let c = barycentric(&pts, &centroid);
+                let c1 = c[(k + 1) % 3];
+                let c2 = c[(k + 2) % 3];
+                g[k] = (2.0 * c2 + c1 - 1.0) / (2.0 - 3.0 * c2 - 3.0 * c1);
+            }
+            for vi in &v {
+                gradients.entry(*vi).or_insert_with(|| {
+                    targets
+                        .iter()
+                        .map(|t| vertex_gradient(dt, *vi, t, self.gradients))
+                        .collect()
+                });
+            }
+            let values = targets
+                .iter()
+                .enumerate()
+                .map(|(i, t)| {
+                    let f = [
+                        target_value(dt, t, v[0]),
+                        target_value(dt, t, v[1]),
+                        target_value(dt, t, v[2]),
+                    ];
+                    let df = [
+                        gradients[&v[0]][i],
+                        gradients[&v[1]][i],
+                        gradients[&v[2]][i],
+                    ];
+                    clough_tocher(&pts, &f, &df, &g, &b)
+                })
+                .collect();
+            re.push(Ok(values));
+        }
+        re
+    }
+}
+impl Interpolant for CloughTocher {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        self.interpolate_resolved(dt, locs, &[ResolvedTarget::Z])
+            .into_iter()
+            .map(|r| r.map(|vs| vs[0]))
+            .collect()
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        match resolve_targets(dt, targets) {
+            Ok(rts) => self.interpolate_resolved(dt, locs, &rts),
+            Err(why) => locs.iter().map(|_| Err(why.clone())).collect(),
+        }
+    }
+}
+
+/// Estimation of z-value with interpolation: Sibson's C1 natural neighbour interpolation
+///
+/// The value of [`NNI`] is blended with the values extrapolated from each natural
+/// neighbour with its gradient (Sibson, 1981; as implemented in CGAL). The surface is
+/// C1 (smooth) except at the vertices, and spherical surfaces are reproduced if the
+/// gradients are exact.
+pub struct SibsonC1 {
+    pub gradients: GradientEstimation,
+}
+impl SibsonC1 {
+    fn interpolate_resolved(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[ResolvedTarget],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let i_nni = NNI { precompute: false };
+        let ws = i_nni.raw_weights(dt, locs);
+        let mut gradients: HashMap<usize, Vec<[f64; 2]>> = HashMap::new();
+        let mut re: Vec<Result<Vec<f64>, StartinError>> = Vec::new();
+        for (p, w) in locs.iter().zip(ws) {
+            let w = match w {
+                Ok(w) => w,
+                Err(why) => {
+                    re.push(Err(why));
+                    continue;
+                }
+            };
+            //-- location on a vertex
+            if w.ws.len() == 1 {
+                re.push(Ok(targets.iter().map(|t| w.value(dt, t)).collect()));
+                continue;
+            }
+            for (vi, _) in &w.ws {
+                gradients.entry(*vi).or_insert_with(|| {
+                    targets
+                        .iter()
+                        .map(|t| vertex_gradient(dt, *vi, t, self.gradients))
+                        .collect()
+                });
+            }
+            let mut values: Vec<f64> = Vec::new();
+            for (i, t) in targets.iter().enumerate() {
+                let (mut term1, mut term2, mut term3) = (0.0, 0.0, 0.0);
+                let mut linear_int: f64 = 0.0;
+                let mut gradient_int: f64 = 0.0;
+                for (vi, lambda) in w.normalised() {
+                    let dx = p[0] - dt.stars[vi].pt[0];
+                    let dy = p[1] - dt.stars[vi].pt[1];
+                    let d2 = dx * dx + dy * dy;
+                    let d = d2.sqrt();
+                    let f = target_value(dt, t, vi);
+                    let g = gradients[&vi][i];
+                    term1 += lambda / d;
+                    term2 += lambda * d2;
+                    term3 += lambda * d;
+                    linear_int += lambda * f;
+                    gradient_int += (lambda / d) * (f + g[0] * dx + g[1] * dy);
+                }
+                let alpha = term3 / term1;
+                gradient_int /= term1;
+                values.push((alpha * linear_int + term2 * gradient_int) / (alpha + term2));
+            }
+            re.push(Ok(values));
+        }
+        re
+    }
+}
+impl Interpolant for SibsonC1 {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        self.interpolate_resolved(dt, locs, &[ResolvedTarget::Z])
+            .into_iter()
+            .map(|r| r.map(|vs| vs[0]))
+            .collect()
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        match resolve_targets(dt, targets) {
+            Ok(rts) => self.interpolate_resolved(dt, locs, &rts),
+            Err(why) => locs.iter().map(|_| Err(why.clone())).collect(),
+        }
+    }
+}
+
+/// The models of variogram that can be fitted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VariogramModel {
+    Spherical,
+    Exponential,
+    Gaussian,
+}
+
+/// A variogram model: `nugget + sill * f(h / range)`, where `sill` is the partial
+/// sill (the total sill is `nugget + sill`), and `range` is the practical range
+/// for the exponential and Gaussian models
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Variogram {
+    pub model: VariogramModel,
+    pub nugget: f64,
+    pub sill: f64,
+    pub range: f64,
+}
+
+/// The empirical (experimental) variogram: for each lag, the mean distance between
+/// the pairs of vertices, the semivariance, and the number of pairs
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmpiricalVariogram {
+    pub lags: Vec<f64>,
+    pub gamma: Vec<f64>,
+    pub counts: Vec<usize>,
+}
+
+/// Computes the empirical variogram of the values of the target at the vertices,
+/// with `nb_lags` lags of width `lag_width` (the pairs of vertices further apart
+/// than `nb_lags * lag_width` are ignored). The lags without pairs are omitted.
+pub fn empirical_variogram(
+    dt: &Triangulation,
+    target: &Target,
+    lag_width: f64,
+    nb_lags: usize,
+) -> Result<EmpiricalVariogram, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let rts = resolve_targets(dt, std::slice::from_ref(target))?;
+    let t = &rts[0];
+    let vs: Vec<usize> = (1..dt.stars.len())
+        .filter(|vi| !dt.stars[*vi].is_deleted() && !target_value(dt, t, *vi).is_nan())
+        .collect();
+    let index = KDBush::create(
+        vs.iter()
+            .map(|vi| (dt.stars[*vi].pt[0], dt.stars[*vi].pt[1]))
+            .collect::<Vec<(f64, f64)>>(),
+        kdbush::DEFAULT_NODE_SIZE,
+    );
+    let maxd = lag_width * nb_lags as f64;
+    let mut sumd: Vec<f64> = vec![0.0; nb_lags];
+    let mut sumg: Vec<f64> = vec![0.0; nb_lags];
+    let mut counts: Vec<usize> = vec![0; nb_lags];
+    for (i, vi) in vs.iter().enumerate() {
+        let fi = target_value(dt, t, *vi);
+        index.within(dt.stars[*vi].pt[0], dt.stars[*vi].pt[1], maxd, |j| {
+            if j <= i {
+                return;
+            }
+            let d = geom::distance2d(&dt.stars[*vi].pt, &dt.stars[vs[j]].pt);
+            let bin = (d / lag_width) as usize;
+            if bin < nb_lags {
+                let df = fi - target_value(dt, t, vs[j]);
+                sumd[bin] += d;
+                sumg[bin] += 0.5 * df * df;
+                counts[bin] += 1;
+            }
+        });
+    }
+    let mut ev = EmpiricalVariogram {
+        lags: Vec::new(),
+        gamma: Vec::new(),
+        counts: Vec::new(),
+    };
+    for bin in 0..nb_lags {
+        if counts[bin] > 0 {
+            ev.lags.push(sumd[bin] / counts[bin] as f64);
+            ev.gamma.push(sumg[bin] / counts[bin] as f64);
+            ev.counts.push(counts[bin]);
+        }
+    }
+    if ev.lags.is_empty() {
+        return Err(StartinError::NotEnoughData);
+    }
+    Ok(ev)
+}
+
+impl Variogram {
+    /// The semivariance at the distance `h`
+    pub fn value(&self, h: f64) -> f64 {
+        if h <= 0.0 {
+            return 0.0;
+        }
+        self.nugget + self.sill * variogram_unit(self.model, h, self.range)
+    }
+
+    /// Fits a model to an empirical variogram, with weighted least squares (the
+    /// weight of a lag is its number of pairs); the nugget and the sill are >= 0
+    pub fn fit(model: VariogramModel, ev: &EmpiricalVariogram) -> Result<Variogram, StartinError> {
+        if ev.lags.len() < 3 {
+            return Err(StartinError::NotEnoughData);
+        }
+        let maxlag = ev.lags.iter().cloned().fold(0.0, f64::max);
+        //-- the nugget and sill are linear for a given range: search the range
+        let steps = 100;
+        let candidates: Vec<f64> = (1..=steps)
+            .map(|i| 2.0 * maxlag * i as f64 / steps as f64)
+            .collect();
+        let mut best = 0;
+        let mut best_err = f64::MAX;
+        for (i, r) in candidates.iter().enumerate() {
+            let (_, _, err) = fit_nugget_sill(model, ev, *r);
+            if err < best_err {
+                best = i;
+                best_err = err;
+            }
+        }
+        //-- refine with a golden-section search around the best candidate
+        let mut lo = if best > 0 {
+            candidates[best - 1]
+        } else {
+            candidates[0] / 2.0
+        };
+        let mut hi = candidates[(best + 1).min(steps - 1)];
+        let gr = (5.0_f64.sqrt() - 1.0) / 2.0;
+        for _ in 0..50 {
+            let r1 = hi - gr * (hi - lo);
+            let r2 = lo + gr * (hi - lo);
+            if fit_nugget_sill(model, ev, r1).2 < fit_nugget_sill(model, ev, r2).2 {
+                hi = r2;
+            } else {
+                lo = r1;
+            }
+        }
+        let range = (lo + hi) / 2.0;
+        let (nugget, sill, _) = fit_nugget_sill(model, ev, range);
+        Ok(Variogram {
+            model,
+            nugget,
+            sill,
+            range,
+        })
+    }
+}
+
+/// The variogram model with a sill of 1 and no nugget
+fn variogram_unit(model: VariogramModel, h: f64, range: f64) -> f64 {
+    let r = h / range;
+    match model {
+        VariogramModel::Spherical => {
+            if r < 1.0 {
+                1.5 * r - 0.5 * r * r * r
+            } else {
+                1.0
+            }
+        }
+        VariogramModel::Exponential => 1.0 - (-3.0 * r).exp(),
+        VariogramModel::Gaussian => 1.0 - (-3.0 * r * r).exp(),
+    }
+}
+
+/// Weighted least squares of the nugget and the sill for a given range,
+/// returns (nugget, sill, weighted squared error)
+fn fit_nugget_sill(model: VariogramModel, ev: &EmpiricalVariogram, range: f64) -> (f64, f64, f64) {
+    let (mut sw, mut sf, mut sff, mut sg, mut sfg) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for i in 0..ev.lags.len() {
+        let w = ev.counts[i] as f64;
+        let f = variogram_unit(model, ev.lags[i], range);
+        sw += w;
+        sf += w * f;
+        sff += w * f * f;
+        sg += w * ev.gamma[i];
+        sfg += w * f * ev.gamma[i];
+    }
+    let det = sw * sff - sf * sf;
+    let mut nugget = if det.abs() > 0.0 {
+        (sff * sg - sf * sfg) / det
+    } else {
+        0.0
+    };
+    let mut sill = if det.abs() > 0.0 {
+        (sw * sfg - sf * sg) / det
+    } else {
+        sfg / sff
+    };
+    if nugget < 0.0 {
+        nugget = 0.0;
+        sill = sfg / sff;
+    }
+    if sill < 0.0 {
+        sill = 0.0;
+        nugget = sg / sw;
+    }
+    let mut err = 0.0;
+    for i in 0..ev.lags.len() {
+        let d = ev.gamma[i] - nugget - sill * variogram_unit(model, ev.lags[i], range);
+        err += ev.counts[i] as f64 * d * d;
+    }
+    (nugget, sill, err)
+}
+
+/// The vertices used to estimate the value at a location
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbourhood {
+    /// the k nearest vertices
+    KNearest(usize),
+    /// the natural neighbours of the location
+    NaturalNeighbours,
+}
+
+/// Estimation of z-value with interpolation: ordinary kriging
+///
+/// The variogram can be obtained with [`empirical_variogram()`] and [`Variogram::fit()`].
+/// The kriging variance is returned by [`OrdinaryKriging::interpolate_with_variance()`].
+pub struct OrdinaryKriging {
+    pub variogram: Variogram,
+    pub neighbourhood: Neighbourhood,
+}
+impl OrdinaryKriging {
+    /// Returns for each location the estimated value and the kriging variance
+    pub fn interpolate_with_variance(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<(f64, f64), StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|r| r.map(|(w, var)| (w.value(dt, &ResolvedTarget::Z), var)))
+            .collect()
+    }
+
+    fn raw_weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<(Weights, f64), StartinError>> {
+        let mut re: Vec<Result<(Weights, f64), StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
+        for p in locs {
+            let ns = match self.neighbourhood {
+                Neighbourhood::NaturalNeighbours => {
+                    match natural_neighbours_of(dt, p, &mut start) {
+                        Ok(NaturalNeighbours::Vertex(vi)) => Ok(vec![vi]),
+                        Ok(NaturalNeighbours::Cavity(l)) => Ok(l.0),
+                        Err(why) => Err(why),
                     }
                 }
-                Err(_e) => re.push(Err(StartinError::OutsideConvexHull)),
+                Neighbourhood::KNearest(k) => k_nearest_vertices(dt, p, k, &mut start, false),
+            };
+            let ns = match ns {
+                Ok(ns) => ns,
+                Err(why) => {
+                    re.push(Err(why));
+                    continue;
+                }
+            };
+            let q: [f64; 3] = [p[0], p[1], 0.0];
+            if geom::distance2d(&q, &dt.stars[ns[0]].pt) <= dt.get_snap_tolerance() || ns.len() == 1
+            {
+                re.push(Ok((Weights::single(ns[0]), 0.0)));
+                continue;
+            }
+            //-- the kriging system, with the Lagrange multiplier
+            let n = ns.len();
+            let mut a: Vec<Vec<f64>> = vec![vec![1.0; n + 1]; n + 1];
+            let mut b: Vec<f64> = vec![1.0; n + 1];
+            for i in 0..n {
+                for j in 0..n {
+                    let h = geom::distance2d(&dt.stars[ns[i]].pt, &dt.stars[ns[j]].pt);
+                    a[i][j] = self.variogram.value(h);
+                }
+                b[i] = self
+                    .variogram
+                    .value(geom::distance2d(&q, &dt.stars[ns[i]].pt));
+            }
+            a[n][n] = 0.0;
+            match geom::solve_linear_system(a, b.clone()) {
+                Some(x) => {
+                    //-- kriging variance: sum(lambda_i * gamma_i0) + mu
+                    let var: f64 = (0..n).map(|i| x[i] * b[i]).sum::<f64>() + x[n];
+                    re.push(Ok((
+                        Weights {
+                            ws: ns.into_iter().zip(x).collect(),
+                            norm: 1.0,
+                        },
+                        var,
+                    )));
+                }
+                None => re.push(Err(StartinError::SingularMatrix)),
+            }
+        }
+        re
+    }
+}
+impl WeightedInterpolant for OrdinaryKriging {
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|(w, _)| w.normalised()))
+            .collect()
+    }
+}
+impl Interpolant for OrdinaryKriging {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        let ws = self
+            .raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.0))
+            .collect();
+        weights_to_z(dt, ws)
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let ws = self
+            .raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.0))
+            .collect();
+        weights_to_targets(dt, ws, targets)
+    }
+}
+
+/// The radial basis functions, the parameter of `Multiquadric` and `Gaussian`
+/// is the shape parameter epsilon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RadialBasis {
+    ThinPlateSpline,
+    Multiquadric(f64),
+    Gaussian(f64),
+}
+
+impl RadialBasis {
+    fn value(&self, r: f64) -> f64 {
+        match self {
+            RadialBasis::ThinPlateSpline => {
+                if r > 0.0 {
+                    r * r * r.ln()
+                } else {
+                    0.0
+                }
+            }
+            RadialBasis::Multiquadric(e) => (1.0 + (e * r) * (e * r)).sqrt(),
+            RadialBasis::Gaussian(e) => (-(e * r) * (e * r)).exp(),
+        }
+    }
+}
+
+/// Estimation of z-value with interpolation: radial basis functions (RBF)
+///
+/// A RBF with a linear polynomial is fitted locally to the vertices of the
+/// neighbourhood of each location. With `Neighbourhood::KNearest` the locations
+/// can be outside the convex hull.
+/// If `smoothing` is 0.0 the surface passes through the vertices, otherwise
+/// it is smoothed (`smoothing` is added to the diagonal of the matrix).
+pub struct RBF {
+    pub basis: RadialBasis,
+    pub neighbourhood: Neighbourhood,
+    pub smoothing: f64,
+}
+impl RBF {
+    fn raw_weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Weights, StartinError>> {
+        let mut re: Vec<Result<Weights, StartinError>> = Vec::new();
+        let mut start: Option<usize> = None;
+        for p in locs {
+            let ns = match self.neighbourhood {
+                Neighbourhood::NaturalNeighbours => {
+                    match natural_neighbours_of(dt, p, &mut start) {
+                        Ok(NaturalNeighbours::Vertex(vi)) => Ok(vec![vi]),
+                        Ok(NaturalNeighbours::Cavity(l)) => Ok(l.0),
+                        Err(why) => Err(why),
+                    }
+                }
+                Neighbourhood::KNearest(k) => k_nearest_vertices(dt, p, k, &mut start, true),
+            };
+            let ns = match ns {
+                Ok(ns) => ns,
+                Err(why) => {
+                    re.push(Err(why));
+                    continue;
+                }
+            };
+            let q: [f64; 3] = [p[0], p[1], 0.0];
+            if ns.len() == 1
+                || (self.smoothing == 0.0
+                    && geom::distance2d(&q, &dt.stars[ns[0]].pt) <= dt.get_snap_tolerance())
+            {
+                re.push(Ok(Weights::single(ns[0])));
+                continue;
+            }
+            //-- the system is symmetric, solving it for the basis at the location
+            //-- gives the weights of the vertices (the coordinates are relative to q)
+            let n = ns.len();
+            let mut a: Vec<Vec<f64>> = vec![vec![0.0; n + 3]; n + 3];
+            let mut b: Vec<f64> = vec![0.0; n + 3];
+            for i in 0..n {
+                let pi = &dt.stars[ns[i]].pt;
+                for j in 0..n {
+                    let r = geom::distance2d(pi, &dt.stars[ns[j]].pt);
+                    a[i][j] = self.basis.value(r);
+                }
+                a[i][i] += self.smoothing;
+                let poly = [1.0, pi[0] - q[0], pi[1] - q[1]];
+                for k in 0..3 {
+                    a[i][n + k] = poly[k];
+                    a[n + k][i] = poly[k];
+                }
+                b[i] = self.basis.value(geom::distance2d(&q, pi));
+            }
+            b[n] = 1.0;
+            match geom::solve_linear_system(a, b) {
+                Some(x) => re.push(Ok(Weights {
+                    ws: ns.into_iter().zip(x).collect(),
+                    norm: 1.0,
+                })),
+                None => re.push(Err(StartinError::SingularMatrix)),
             }
         }
         re
     }
 }
+impl WeightedInterpolant for RBF {
+    fn weights(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Vec<(usize, f64)>, StartinError>> {
+        self.raw_weights(dt, locs)
+            .into_iter()
+            .map(|w| w.map(|w| w.normalised()))
+            .collect()
+    }
+}
+impl Interpolant for RBF {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_z(dt, ws)
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        let ws = self.raw_weights(dt, locs);
+        weights_to_targets(dt, ws, targets)
+    }
+}
+
+/// How values are extrapolated outside the convex hull
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtrapolationMethod {
+    /// the value at the projection of the location on the closest edge of the convex hull
+    HullEdgeProjection,
+    /// the plane of the triangle incident to the closest edge of the convex hull
+    PlanarExtension,
+    /// the value of the closest vertex
+    NearestNeighbour,
+}
+
+/// A value that was interpolated (inside the convex hull), or extrapolated
+#[derive(Debug, Clone, PartialEq)]
+pub enum Estimate<T> {
+    Interpolated(T),
+    Extrapolated(T),
+}
+
+impl<T> Estimate<T> {
+    pub fn value(&self) -> &T {
+        match self {
+            Estimate::Interpolated(v) | Estimate::Extrapolated(v) => v,
+        }
+    }
+
+    pub fn into_value(self) -> T {
+        match self {
+            Estimate::Interpolated(v) | Estimate::Extrapolated(v) => v,
+        }
+    }
+
+    pub fn is_extrapolated(&self) -> bool {
+        matches!(self, Estimate::Extrapolated(_))
+    }
+}
+
+/// Wraps an [`Interpolant`] so that the locations outside the convex hull (for which
+/// [`StartinError::OutsideConvexHull`] is returned) are extrapolated with `method`, if they are
+/// at most at `max_distance` from the convex hull (`f64::INFINITY` for no limit).
+/// [`Extrapolating::interpolate_flagged()`] tells which values were extrapolated.
+/// If the triangulation is not initialised (it has no convex hull),
+/// [`StartinError::EmptyTriangulation`] is returned instead of extrapolating.
+pub struct Extrapolating<I: Interpolant> {
+    pub interpolant: I,
+    pub method: ExtrapolationMethod,
+    pub max_distance: f64,
+}
+impl<I: Interpolant> Extrapolating<I> {
+    /// Same as [`Interpolant::interpolate()`] but each value is flagged as interpolated or extrapolated
+    pub fn interpolate_flagged(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<Estimate<f64>, StartinError>> {
+        let re = self.interpolant.interpolate(dt, locs);
+        re.into_iter()
+            .zip(locs)
+            .map(|(r, p)| match r {
+                Err(StartinError::OutsideConvexHull) => self
+                    .extrapolate(dt, p, &[ResolvedTarget::Z])
+                    .map(|vs| Estimate::Extrapolated(vs[0])),
+                _ => r.map(Estimate::Interpolated),
+            })
+            .collect()
+    }
+
+    /// Same as [`Interpolant::interpolate_targets()`] but each result is flagged
+    /// as interpolated or extrapolated
+    pub fn interpolate_targets_flagged(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Estimate<Vec<f64>>, StartinError>> {
+        let re = self.interpolant.interpolate_targets(dt, locs, targets);
+        let rts = match resolve_targets(dt, targets) {
+            Ok(rts) => rts,
+            Err(why) => return locs.iter().map(|_| Err(why.clone())).collect(),
+        };
+        re.into_iter()
+            .zip(locs)
+            .map(|(r, p)| match r {
+                Err(StartinError::OutsideConvexHull) => {
+                    self.extrapolate(dt, p, &rts).map(Estimate::Extrapolated)
+                }
+                _ => r.map(Estimate::Interpolated),
+            })
+            .collect()
+    }
+
+    fn extrapolate(
+        &self,
+        dt: &Triangulation,
+        p: &[f64; 2],
+        targets: &[ResolvedTarget],
+    ) -> Result<Vec<f64>, StartinError> {
+        if !dt.is_init {
+            return Err(StartinError::EmptyTriangulation);
+        }
+        let q: [f64; 3] = [p[0], p[1], 0.0];
+        //-- closest edge of the convex hull
+        let ch = &dt.stars[0].link;
+        let mut closest: (f64, usize, usize, f64) = (f64::MAX, 0, 0, 0.0);
+        for (i, a) in ch.iter().enumerate() {
+            let b = ch[ch.next_index(i)];
+            let pa = &dt.stars[*a].pt;
+            let pb = &dt.stars[b].pt;
+            let ab = [pb[0] - pa[0], pb[1] - pa[1]];
+            let t = (((q[0] - pa[0]) * ab[0] + (q[1] - pa[1]) * ab[1])
+                / (ab[0] * ab[0] + ab[1] * ab[1]))
+                .clamp(0.0, 1.0);
+            let proj = [pa[0] + t * ab[0], pa[1] + t * ab[1]];
+            let d = geom::distance2d(&q, &proj);
+            if d < closest.0 {
+                closest = (d, *a, b, t);
+            }
+        }
+        let (d, a, b, t) = closest;
+        if d > self.max_distance {
+            return Err(StartinError::OutsideConvexHull);
+        }
+        let values = match self.method {
+            ExtrapolationMethod::HullEdgeProjection => targets
+                .iter()
+                .map(|rt| (1.0 - t) * target_value(dt, rt, a) + t * target_value(dt, rt, b))
+                .collect(),
+            ExtrapolationMethod::PlanarExtension => {
+                //-- the finite triangle is on the other side of the edge than the infinite vertex
+                let c = dt.stars[b].link.get_next_vertex(a).unwrap();
+                let tr = [b, a, c];
+                let pts: Vec<&[f64]> = tr.iter().map(|vi| &dt.stars[*vi].pt[..]).collect();
+                let bc = barycentric(&pts, &q);
+                targets
+                    .iter()
+                    .map(|rt| (0..3).map(|i| bc[i] * target_value(dt, rt, tr[i])).sum())
+                    .collect()
+            }
+            ExtrapolationMethod::NearestNeighbour => {
+                let tr = dt.walk_from(&q, dt.start_vertex(Some(a)));
+                let vi = dt.closest_point_in_star(&tr, p[0], p[1]);
+                targets.iter().map(|rt| target_value(dt, rt, vi)).collect()
+            }
+        };
+        Ok(values)
+    }
+}
+impl<I: Interpolant> Interpolant for Extrapolating<I> {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, StartinError>> {
+        self.interpolate_flagged(dt, locs)
+            .into_iter()
+            .map(|r| r.map(|e| e.into_value()))
+            .collect()
+    }
+
+    fn interpolate_targets(
+        &self,
+        dt: &Triangulation,
+        locs: &Vec<[f64; 2]>,
+        targets: &[Target],
+    ) -> Vec<Result<Vec<f64>, StartinError>> {
+        self.interpolate_targets_flagged(dt, locs, targets)
+            .into_iter()
+            .map(|r| r.map(|e| e.into_value()))
+            .collect()
+    }
+}
+
+/// The results of a leave-one-out cross-validation, see [`cross_validation()`]
+#[derive(Debug, Clone)]
+pub struct CrossValidation {
+    /// for each vertex validated: its ID and its residual (interpolated z - real z),
+    /// or the error if it could not be interpolated (eg the vertices on the convex hull
+    /// are outside the convex hull once they are removed)
+    pub residuals: Vec<(usize, Result<f64, StartinError>)>,
+    /// number of vertices that could be interpolated (used for the statistics)
+    pub count: usize,
+    /// root-mean-square error
+    pub rmse: f64,
+    /// mean absolute error
+    pub mae: f64,
+    /// largest absolute error
+    pub max: f64,
+}
+
+/// Leave-one-out cross-validation of an interpolant: each vertex is removed, its z-value
+/// is interpolated with the other vertices, and it is then put back.
+/// `vertices` are the IDs of the vertices to validate (eg a sample), if `None` then all
+/// the vertices are validated.
+///
+/// The triangulation is not modified, the vertices are removed from a copy of it (without
+/// the attributes). The spatial index of the vertices (used by [`IDW`]) is built only once,
+/// the vertex removed is ignored by it.
+/// The statistics are `f64::NAN` if no vertex could be interpolated.
+/// Returns [`StartinError::EmptyTriangulation`] if the triangulation is not initialised.
+pub fn cross_validation(
+    interpolant: &impl Interpolant,
+    dt: &Triangulation,
+    vertices: Option<&[usize]>,
+) -> Result<CrossValidation, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let vs: Vec<usize> = match vertices {
+        Some(vs) => {
+            for vi in vs {
+                if *vi == 0 {
+                    return Err(StartinError::VertexInfinite);
+                }
+                if dt.is_vertex_removed(*vi)? {
+                    return Err(StartinError::VertexRemoved);
+                }
+            }
+            vs.to_vec()
+        }
+        None => (1..dt.stars.len())
+            .filter(|vi| !dt.stars[*vi].is_deleted())
+            .collect(),
+    };
+    let mut work = dt.copy_geometry();
+    //-- the removals and insertions invalidate the cached index of the copy, the IDs do
+    //-- not change so the same index is put back each time
+    let mut index = Some(VertexIndex::new(dt));
+    let mut residuals: Vec<(usize, Result<f64, StartinError>)> = Vec::with_capacity(vs.len());
+    for vi in vs {
+        let p = dt.stars[vi].pt;
+        work.remove(vi)?;
+        if let Some(mut idx) = index.take() {
+            idx.excluded = Some(vi);
+            let _ = work.vertex_index.set(idx);
+        }
+        let r = interpolant
+            .interpolate(&work, &vec![[p[0], p[1]]])
+            .pop()
+            .unwrap()
+            .map(|z| z - p[2]);
+        residuals.push((vi, r));
+        index = work.vertex_index.take();
+        //-- the vertex gets back its ID, unless the triangulation was not
+        //-- initialised anymore (then the IDs can change and we start from a new copy)
+        match work.insert_one_pt(p[0], p[1], p[2]) {
+            Ok(v) if v == vi => (),
+            _ => work = dt.copy_geometry(),
+        }
+    }
+    let errors: Vec<f64> = residuals
+        .iter()
+        .filter_map(|(_, r)| r.as_ref().ok().map(|e| e.abs()))
+        .collect();
+    let count = errors.len();
+    let (rmse, mae, max) = if count == 0 {
+        (f64::NAN, f64::NAN, f64::NAN)
+    } else {
+        let n = count as f64;
+        (
+            (errors.iter().map(|e| e * e).sum::<f64>() / n).sqrt(),
+            errors.iter().sum::<f64>() / n,
+            errors.iter().fold(0.0, |m: f64, e| m.max(*e)),
+        )
+    };
+    Ok(CrossValidation {
+        residuals,
+        count,
+        rmse,
+        mae,
+        max,
+    })
+}
+
+/// The k nearest vertices of `p` (closest first), found with a best-first search in
+/// the triangulation from the closest vertex (the i-th nearest vertex is always
+/// adjacent to one of the i-1 nearest).
+/// If `extrapolate` then `p` can be outside the convex hull.
+fn k_nearest_vertices(
+    dt: &Triangulation,
+    p: &[f64; 2],
+    k: usize,
+    start: &mut Option<usize>,
+    extrapolate: bool,
+) -> Result<Vec<usize>, StartinError> {
+    let closest = match dt.closest_point_from(p[0], p[1], *start) {
+        Ok(vi) => vi,
+        Err(StartinError::OutsideConvexHull) if extrapolate => {
+            //-- the greedy search works also from the finite vertices of an infinite triangle
+            let tr = dt.walk_from(&[p[0], p[1], 0.0], dt.start_vertex(*start));
+            dt.closest_point_in_star(&tr, p[0], p[1])
+        }
+        Err(why) => return Err(why),
+    };
+    *start = Some(closest);
+    let q: [f64; 3] = [p[0], p[1], 0.0];
+    let mut re: Vec<usize> = Vec::new();
+    let mut candidates: Vec<(f64, usize)> = vec![(0.0, closest)];
+    let mut seen: Vec<usize> = vec![0, closest];
+    while re.len() < k && !candidates.is_empty() {
+        let imin = (0..candidates.len())
+            .min_by(|i, j| candidates[*i].0.total_cmp(&candidates[*j].0))
+            .unwrap();
+        let (_, vi) = candidates.swap_remove(imin);
+        re.push(vi);
+        for v in dt.stars[vi].link.iter() {
+            if !seen.contains(v) {
+                seen.push(*v);
+                candidates.push((geom::distance2d_squared(&q, &dt.stars[*v].pt), *v));
+            }
+        }
+    }
+    Ok(re)
+}
+
+/// The barycentric coordinates of `q` in the triangle `pts`
+fn barycentric(pts: &[&[f64]], q: &[f64]) -> [f64; 3] {
+    let a = geom::area2d_triangle(pts[0], pts[1], pts[2]);
+    [
+        geom::area2d_triangle(q, pts[1], pts[2]) / a,
+        geom::area2d_triangle(pts[0], q, pts[2]) / a,
+        geom::area2d_triangle(pts[0], pts[1], q) / a,
+    ]
+}
+
+/// Evaluates the Clough-Tocher cubic at the barycentric coordinates `b`,
+/// the values and gradients at the 3 vertices are `f` and `df`, and `g` defines
+/// the direction of the cross-boundary derivatives of the 3 edges.
+/// The control points are those of the cubic Bezier triangles (c3000 is f[0], etc.).
+fn clough_tocher(
+    pts: &[&[f64]],
+    f: &[f64; 3],
+    df: &[[f64; 2]; 3],
+    g: &[f64; 3],
+    b: &[f64; 3],
+) -> f64 {
+    let e12 = [pts[1][0] - pts[0][0], pts[1][1] - pts[0][1]];
+    let e23 = [pts[2][0] - pts[1][0], pts[2][1] - pts[1][1]];
+    let e31 = [pts[0][0] - pts[2][0], pts[0][1] - pts[2][1]];
+    let df12 = df[0][0] * e12[0] + df[0][1] * e12[1];
+    let df21 = -(df[1][0] * e12[0] + df[1][1] * e12[1]);
+    let df23 = df[1][0] * e23[0] + df[1][1] * e23[1];
+    let df32 = -(df[2][0] * e23[0] + df[2][1] * e23[1]);
+    let df31 = df[2][0] * e31[0] + df[2][1] * e31[1];
+    let df13 = -(df[0][0] * e31[0] + df[0][1] * e31[1]);
+
+    let c3000 = f[0];
+    let c2100 = (df12 + 3.0 * c3000) / 3.0;
+    let c2010 = (df13 + 3.0 * c3000) / 3.0;
+    let c0300 = f[1];
+    let c1200 = (df21 + 3.0 * c0300) / 3.0;
+    let c0210 = (df23 + 3.0 * c0300) / 3.0;
+    let c0030 = f[2];
+    let c1020 = (df31 + 3.0 * c0030) / 3.0;
+    let c0120 = (df32 + 3.0 * c0030) / 3.0;
+
+    let c2001 = (c2100 + c2010 + c3000) / 3.0;
+    let c0201 = (c1200 + c0300 + c0210) / 3.0;
+    let c0021 = (c1020 + c0120 + c0030) / 3.0;
+
+    let c0111 = (g[0] * (-c0300 + 3.0 * c0210 - 3.0 * c0120 + c0030)
+        + (-c0300 + 2.0 * c0210 - c0120 + c0021 + c0201))
+        / 2.0;
+    let c1011 = (g[1] * (-c0030 + 3.0 * c1020 - 3.0 * c2010 + c3000)
+        + (-c0030 + 2.0 * c1020 - c2010 + c2001 + c0021))
+        / 2.0;
+    let c1101 = (g[2] * (-c3000 + 3.0 * c2100 - 3.0 * c1200 + c0300)
+        + (-c3000 + 2.0 * c2100 - c1200 + c2001 + c0201))
+        / 2.0;
+
+    let c1002 = (c1101 + c1011 + c2001) / 3.0;
+    let c0102 = (c1101 + c0111 + c0201) / 3.0;
+    let c0012 = (c1011 + c0111 + c0021) / 3.0;
+    let c0003 = (c1002 + c0102 + c0012) / 3.0;
+
+    //-- barycentric coordinates in the sub-triangle (b4 is for the centroid)
+    let minval = b[0].min(b[1]).min(b[2]);
+    let b1 = b[0] - minval;
+    let b2 = b[1] - minval;
+    let b3 = b[2] - minval;
+    let b4 = 3.0 * minval;
+
+    b1.powi(3) * c3000
+        + 3.0 * b1 * b1 * b2 * c2100
+        + 3.0 * b1 * b1 * b3 * c2010
+        + 3.0 * b1 * b1 * b4 * c2001
+        + 3.0 * b1 * b2 * b2 * c1200
+        + 6.0 * b1 * b2 * b4 * c1101
+        + 3.0 * b1 * b3 * b3 * c1020
+        + 6.0 * b1 * b3 * b4 * c1011
+        + 3.0 * b1 * b4 * b4 * c1002
+        + b2.powi(3) * c0300
+        + 3.0 * b2 * b2 * b3 * c0210
+        + 3.0 * b2 * b2 * b4 * c0201
+        + 3.0 * b2 * b3 * b3 * c0120
+        + 6.0 * b2 * b3 * b4 * c0111
+        + 3.0 * b2 * b4 * b4 * c0102
+        + b3.powi(3) * c0030
+        + 3.0 * b3 * b3 * b4 * c0021
+        + 3.0 * b3 * b4 * b4 * c0012
+        + b4.powi(3) * c0003
+}
@@ -13,7 +13,13 @@
 //!
 //! Robust arithmetic for the geometric predicates are used ([Shewchuk's predicates](https://www.cs.cmu.edu/~quake/robust.html), well the [Rust port of the code (robust crate)](https://crates.io/crates/robust)), so startin is robust and shouldn't crash (touch wood).
 //!
-//! There are a few interpolation functions implemented: (1) nearest-neighbour, (2) linear in TIN, (3) Laplace, (4) natural neighbour (aka Sibson's interpolation), (5) IDW.
+//! There are a few interpolation functions implemented: (1) nearest-neighbour, (2) linear in TIN, (3) Laplace, (4) natural neighbour (aka Sibson's interpolation), (5) IDW with search-radius, k-nearest, or sector search, (6) Clough-Tocher, (7) Sibson's C1 natural neighbour, (8) ordinary kriging, (9) radial basis functions.
+//!
+//! The z-values of the vertices can be smoothed (Laplacian, Taubin, or feature-preserving bilateral smoothing), see the module [`smoothing`].
+//! The terrain derivatives (slope, aspect, and curvatures) are in the module [`terrain`], and contour lines can be extracted with the module [`contours`].
+//! Elevation profiles along polylines, exact for the TIN, are in the module [`profile`], and the line-of-sight, viewshed, and ray intersection queries in the module [`visibility`].
+//! The flow routing on the TIN (flow directions and accumulation, drainage lines, pits and catchments, depression filling and breaching) is in the module [`hydrology`].
+//! The cut and fill volumes between two triangulations (eg before and after earthworks) are in the module [`cutfill`].
 //!
 //!
 //! # Web-demo with WebAssembly
@@ -98,35 +104,55 @@
 //! }
 //! ```
 
+pub mod attributes;
+pub mod contours;
+pub mod cutfill;
 pub mod geom;
+pub mod hydrology;
 pub mod interpolation;
+pub mod profile;
+pub mod smoothing;
+pub mod terrain;
+pub mod visibility;
 
 #[cfg(feature = "c_api")]
 mod c_interface;
 
+use attributes::AttributeColumn;
+use attributes::AttributeStore;
+use attributes::AttributeType;
+use attributes::AttributeValue;
+use attributes::ColumnValues;
+use attributes::InheritanceRules;
+use attributes::KeyedAttributes;
+use attributes::TriangleInheritance;
+use interpolation::VertexIndex;
+
 use rand::prelude::thread_rng;
 use rand::Rng;
-use serde_json::Map;
 
-use serde_json::json;
 use serde_json::Value;
 
 use std::fmt;
 use std::fs::File;
 use std::io::Write;
+use std::sync::OnceLock;
 
 /// Errors that arise while using startin
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum StartinError {
     EmptyTriangulation,
     OutsideConvexHull,
     SearchCircleEmpty,
     TriangleNotPresent,
+    EdgeNotPresent,
     VertexInfinite,
     VertexRemoved,
     VertexUnknown,
     TinHasNoAttributes,
     WrongAttribute,
+    NotEnoughData,
+    SingularMatrix,
 }
 
 /// Possibilities for the insertion (with `insert()`)
@@ -173,6 +199,67 @@ impl Triangle {
     }
 }
 
+/// Parses a schema given as names and data types (as strings)
+fn parse_schema(
+    att_schema: &[(String, String)],
+) -> Result<Vec<(String, AttributeType)>, StartinError> {
+    let mut schema: Vec<(String, AttributeType)> = Vec::new();
+    for each in att_schema {
+        match AttributeType::from_name(&each.1) {
+            Some(dtype) => match schema.iter().find(|(name, _)| *name == each.0) {
+                Some((_, dtype2)) => {
+                    if *dtype2 != dtype {
+                        return Err(StartinError::WrongAttribute);
+                    }
+                }
+                None => schema.push((each.0.clone(), dtype)),
+            },
+            None => return Err(StartinError::WrongAttribute),
+        }
+    }
+    Ok(schema)
+}
+
+/// The PLY type of an attribute (String are not written)
+fn ply_type(dtype: AttributeType) -> Option<&'static str> {
+    match dtype {
+        AttributeType::F64 => Some("double"),
+        AttributeType::I64 => Some("int"),
+        AttributeType::U64 | AttributeType::Bool => Some("uint"),
+        AttributeType::String => None,
+    }
+}
+
+/// The PLY value of an attribute, with a space before (unset ones are 0)
+fn ply_value(v: Option<&AttributeValue>) -> String {
+    match v {
+        Some(AttributeValue::Bool(b)) => format!(" {}", *b as u8),
+        Some(AttributeValue::String(_)) => String::new(),
+        Some(x) => format!(" {}", x),
+        None => " 0".to_string(),
+    }
+}
+
+/// The key of a triangle for its attributes: the same rotation whatever the first vertex
+fn triangle_key(v: &[usize; 3]) -> [usize; 3] {
+    if v[0] < v[1] && v[0] < v[2] {
+        [v[0], v[1], v[2]]
+    } else if v[1] < v[2] {
+        [v[1], v[2], v[0]]
+    } else {
+        [v[2], v[0], v[1]]
+    }
+}
+
+/// The key of an edge for its attributes
+fn edge_key(a: usize, b: usize) -> [usize; 2] {
+    if a < b {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
 impl fmt::Display for Triangle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({}, {}, {})", self.v[0], self.v[1], self.v[2])
@@ -311,6 +398,7 @@ impl fmt::Display for Link {
 /// A triangulation is a collection of Stars, each Star has its (x,y,z) coordinates
 /// and a Link (an array of adjacent vertices, ordered CCW)
 #[repr(C)]
+#[derive(Clone)]
 struct Star {
     pt: [f64; 3],
     link: Link,
@@ -333,8 +421,10 @@ impl Star {
 #[repr(C)]
 pub struct Triangulation {
     stars: Vec<Star>,
-    attributes: Option<Vec<Value>>,
-    attributes_schema: Vec<(String, String)>,
+    attributes: Option<AttributeStore>,
+    triangle_attributes: KeyedAttributes<[usize; 3]>,
+    edge_attributes: KeyedAttributes<[usize; 2]>,
+    inheritance: InheritanceRules,
     snaptol: f64,
     cur: usize,
     is_init: bool,
@@ -342,6 +432,7 @@ pub struct Triangulation {
     robust_predicates: bool,
     removed_indices: Vec<usize>,
     duplicates_handling: DuplicateHandling,
+    vertex_index: OnceLock<VertexIndex>,
 }
 
 impl Default for Triangulation {
@@ -358,7 +449,9 @@ impl Triangulation {
         Triangulation {
             stars: l,
             attributes: None,
-            attributes_schema: Vec::new(),
+            triangle_attributes: KeyedAttributes::new(),
+            edge_attributes: KeyedAttributes::new(),
+            inheritance: InheritanceRules::default(),
             snaptol: 0.001,
             cur: 0,
             is_init: false,
@@ -366,10 +459,12 @@ impl Triangulation {
             robust_predicates: true,
             removed_indices: es,
             duplicates_handling: DuplicateHandling::First,
+            vertex_index: OnceLock::new(),
         }
     }
 
     fn insert_one_pt_init_phase(&mut self, x: f64, y: f64, z: f64) -> Result<usize, (usize, bool)> {
+        self.vertex_index.take();
         let p: [f64; 3] = [x, y, z];
         for i in 1..self.stars.len() {
             if geom::distance2d_squared(&self.stars[i].pt, &p) <= (self.snaptol * self.snaptol) {
@@ -434,7 +529,7 @@ impl Triangulation {
             }
         }
         match &mut self.attributes {
-            Some(x) => x.push(json!({})),
+            Some(x) => x.push_row(),
             _ => (),
         };
 
@@ -547,10 +642,6 @@ impl Triangulation {
         self.insert_one_pt_z_handling(px, py, pz, true)
     }
 
-    fn insert_one_pt_interpol(&mut self, px: f64, py: f64) -> Result<usize, (usize, bool)> {
-        self.insert_one_pt_z_handling(px, py, 0.0, false)
-    }
-
     fn insert_one_pt_z_handling(
         &mut self,
         px: f64,
@@ -586,6 +677,7 @@ impl Triangulation {
             }
         }
         //-- ok we now insert the point in the data structure
+        self.vertex_index.take();
         let pi: usize;
         if self.removed_indices.is_empty() {
             self.stars.push(Star::new(px, py, pz));
@@ -604,7 +696,13 @@ impl Triangulation {
         self.cur = pi;
         //-- extra attributes
         match &mut self.attributes {
-            Some(x) => x.push(json!({})),
+            Some(x) => {
+                if pi < x.len() {
+                    x.reset_row(pi);
+                } else {
+                    x.push_row();
+                }
+            }
             _ => (),
         }
         Ok(pi)
@@ -730,6 +828,21 @@ impl Triangulation {
         self.stars[tr.v[2]].link.insert_after_v(pi, tr.v[0]);
         //-- put infinite vertex first in list
         self.stars[pi].link.infinite_first();
+        //-- triangle attributes
+        if !self.triangle_attributes.is_empty() {
+            let row = self.triangle_attributes.take(&triangle_key(&tr.v));
+            if let Some(row) = row {
+                if self.inheritance.flip13 != TriangleInheritance::Drop {
+                    for i in 0..3 {
+                        let t = [pi, tr.v[i], tr.v[(i + 1) % 3]];
+                        if !t.contains(&0) {
+                            self.triangle_attributes
+                                .insert_row(triangle_key(&t), row.clone());
+                        }
+                    }
+                }
+            }
+        }
     }
 
     fn flip31(&mut self, vi: usize) {
@@ -771,46 +884,91 @@ impl Triangulation {
         }
     }
 
-    /// TODO: write the docs
+    /// Returns the schema of the extra attributes, a list of names and data types
+    /// (eg `("intensity", "f64")`).
     pub fn get_attributes_schema(&self) -> Vec<(String, String)> {
-        self.attributes_schema.clone()
+        match &self.attributes {
+            Some(x) => x
+                .schema()
+                .iter()
+                .map(|(name, dtype)| (name.clone(), dtype.to_string()))
+                .collect(),
+            None => Vec::new(),
+        }
     }
 
     /// Configure the extra attributes that each vertex can store.
     /// Each entry is a name (eg "classification") and a data type
     /// (the allowed types are: "f64", "i64", "u64", "bool", and "String" (given as a String)).
-    /// This resets all the extra attributes that were potentially stored with a previous schema.
+    ///
+    /// The schema replaces the previous one: the attributes already stored for the
+    /// names that are in both schemas are kept, the new ones are unset for all the vertices,
+    /// and those not in `att_schema` are removed.
+    /// A [`StartinError::WrongAttribute`] is returned (and nothing is modified) if a type
+    /// is unknown, or if an existing attribute is given a different type
+    /// (use [`Triangulation::remove_attribute()`] first).
     pub fn set_attributes_schema(
         &mut self,
         att_schema: Vec<(String, String)>,
     ) -> Result<(), StartinError> {
-        let dtypes_allowed: Vec<String> = vec![
-            "f64".to_string(),
-            "i64".to_string(),
-            "u64".to_string(),
-            "bool".to_string(),
-            "String".to_string(),
-        ];
-        for each in &att_schema {
-            if dtypes_allowed.iter().any(|e| *e == *each.1) {
-                self.attributes_schema
-                    .push((each.0.clone(), each.1.clone()));
-            } else {
-                return Err(StartinError::WrongAttribute);
+        let schema = parse_schema(&att_schema)?;
+        match &mut self.attributes {
+            Some(x) => x.set_schema(&schema),
+            None => {
+                self.attributes = Some(AttributeStore::new(&schema, self.stars.len()));
+                Ok(())
             }
         }
-        //-- reset all the extra attributes
-        self.attributes = Some(vec![json!({}); self.stars.len()]);
-        Ok(())
     }
 
+    /// Adds the attribute `name` of type `dtype` to the schema, the values already
+    /// stored for the other attributes are kept.
+    /// If `default` is given then all the vertices get that value, otherwise the
+    /// attribute is unset for all of them.
+    /// Adding an attribute that already exists with the same type does nothing,
+    /// if the type is different then a [`StartinError::WrongAttribute`] is returned
+    /// (as it is if `default` is not of type `dtype`).
+    pub fn add_attribute(
+        &mut self,
+        name: &str,
+        dtype: AttributeType,
+        default: Option<AttributeValue>,
+    ) -> Result<(), StartinError> {
+        let mut unset: Vec<usize> = self.removed_indices.clone();
+        unset.push(0);
+        let len = self.stars.len();
+        self.attributes
+            .get_or_insert_with(|| AttributeStore::new(&[], len))
+            .add_column(name, dtype, default, &unset)
+    }
+
+    /// Removes the attribute `name` (and all its values) from the schema.
+    pub fn remove_attribute(&mut self, name: &str) -> Result<(), StartinError> {
+        match &mut self.attributes {
+            Some(x) => x.remove_column(name),
+            None => Err(StartinError::TinHasNoAttributes),
+        }
+    }
+
+    /// Renames the attribute `name` to `newname`, its values are kept.
+    /// A [`StartinError::WrongAttribute`] is returned if `name` doesn't exist or
+    /// if `newname` is already used.
+    pub fn rename_attribute(&mut self, name: &str, newname: &str) -> Result<(), StartinError> {
+        match &mut self.attributes {
+            Some(x) => x.rename_column(name, newname),
+            None => Err(StartinError::TinHasNoAttributes),
+        }
+    }
+
+    /// Returns the extra attributes of the vertex `vi` as a JSON object
+    /// (only the attributes that were set are present).
     pub fn get_vertex_attributes(&self, vi: usize) -> Result<Value, StartinError> {
         match self.is_vertex_removed(vi) {
             Err(why) => Err(why),
             Ok(b) => match b {
                 true => Err(StartinError::VertexRemoved),
                 false => match &self.attributes {
-                    Some(x) => Ok(x.get(vi).unwrap().clone()),
+                    Some(x) => Ok(x.get_json(vi)),
                     None => Err(StartinError::TinHasNoAttributes),
                 },
             },
@@ -822,78 +980,270 @@ impl Triangulation {
             Err(why) => Err(why),
             Ok(_b) => match &mut self.attributes {
                 Some(x) => {
-                    *x.get_mut(vi).unwrap() = json!({});
-                    return Ok(true);
-                }
-                None => {
-                    return Err(StartinError::TinHasNoAttributes);
+                    x.reset_row(vi);
+                    Ok(true)
                 }
+                None => Err(StartinError::TinHasNoAttributes),
             },
         }
     }
 
+    /// Adds/updates the extra attributes of the vertex `vi` from a JSON object.
+    /// The members that are not in the schema, or that are not of the
+    /// type of the schema, are ignored.
+    /// Returns `Ok(false)` if `a` is not a JSON object.
     pub fn add_vertex_attributes(&mut self, vi: usize, a: Value) -> Result<bool, StartinError> {
         match self.is_vertex_removed(vi) {
             Err(why) => Err(why),
             Ok(_b) => match &mut self.attributes {
-                Some(x) => {
-                    if a.is_object() == false {
-                        return Ok(false);
-                    }
-                    let mut a2: Map<String, Value> = Map::new();
-                    let a1 = a.as_object().unwrap();
-                    for (p, v2) in a1 {
-                        let c = self
-                            .attributes_schema
-                            .iter()
-                            .position(|(first, _)| first == p);
-                        if c.is_some() {
-                            match self.attributes_schema.get(c.unwrap()).unwrap().1.as_ref() {
-                                "f64" => {
-                                    if v2.is_number() {
-                                        a2.insert(p.to_string(), v2.clone());
-                                    }
-                                }
-                                "i64" => {
-                                    if v2.is_i64() {
-                                        a2.insert(p.to_string(), v2.clone());
-                                    }
-                                }
-                                "u64" => {
-                                    if v2.is_u64() {
-                                        a2.insert(p.to_string(), v2.clone());
-                                    }
-                                }
-                                "String" => {
-                                    if v2.is_string() {
-                                        a2.insert(p.to_string(), v2.clone());
-                                    }
-                                }
-                                "bool" => {
-                                    if v2.is_boolean() {
-                                        a2.insert(p.to_string(), v2.clone());
-                                    }
-                                }
-                                _ => continue,
-                            }
-                        }
-                    }
-                    let v = x.get_mut(vi).unwrap();
-                    let vo = v.as_object_mut().unwrap();
-                    vo.append(&mut a2);
-                    Ok(true)
-                }
-                None => {
-                    return Err(StartinError::TinHasNoAttributes);
-                }
+                Some(x) => Ok(x.set_json(vi, &a)),
+                None => Err(StartinError::TinHasNoAttributes),
             },
         }
     }
 
-    /// Returns a [`Vec`]<[`Vec`]<[`Value`]>> of all the vertex attributes
+    /// Returns a [`Vec`]<[`Value`]> of all the vertex attributes
     /// (including the infinite one and the removed ones)
     pub fn all_attributes(&self) -> Option<Vec<Value>> {
-        self.attributes.clone()
+        self.attributes
+            .as_ref()
+            .map(|x| (0..x.len()).map(|i| x.get_json(i)).collect())
+    }
+
+    /// Returns the value of the attribute `name` of the vertex `vi`,
+    /// `None` if no value was set for that vertex.
+    /// A [`StartinError::WrongAttribute`] is returned if `name` is not in the schema.
+    pub fn get_vertex_attribute(
+        &self,
+        vi: usize,
+        name: &str,
+    ) -> Result<Option<AttributeValue>, StartinError> {
+        match self.is_vertex_removed(vi) {
+            Err(why) => Err(why),
+            Ok(b) => match b {
+                true => Err(StartinError::VertexRemoved),
+                false => match &self.attributes {
+                    Some(x) => x.get(vi, name),
+                    None => Err(StartinError::TinHasNoAttributes),
+                },
+            },
+        }
+    }
+
+    /// Sets the value of the attribute `name` of the vertex `vi`.
+    /// A [`StartinError::WrongAttribute`] is returned if `name` is not in the schema,
+    /// or if the type of `value` is not the one of the schema.
+    pub fn set_vertex_attribute(
+        &mut self,
+        vi: usize,
+        name: &str,
+        value: AttributeValue,
+    ) -> Result<(), StartinError> {
+        match self.is_vertex_removed(vi) {
+            Err(why) => Err(why),
+            Ok(b) => match b {
+                true => Err(StartinError::VertexRemoved),
+                false => match &mut self.attributes {
+                    Some(x) => x.set(vi, name, value),
+                    None => Err(StartinError::TinHasNoAttributes),
+                },
+            },
+        }
+    }
+
+    /// Returns the value of the attribute `name` of the vertex `vi` as a f64
+    /// (works for all numeric types and bool), `None` if it was not set.
+    pub fn get_vertex_attribute_f64(
+        &self,
+        vi: usize,
+        name: &str,
+    ) -> Result<Option<f64>, StartinError> {
+        match self.get_vertex_attribute(vi, name)? {
+            Some(v) => match v.as_f64() {
+                Some(f) => Ok(Some(f)),
+                None => Err(StartinError::WrongAttribute),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the whole column of the attribute `name`, with one value per vertex
+    /// (including the infinite one and the removed ones).
+    pub fn attribute_column(&self, name: &str) -> Result<&AttributeColumn, StartinError> {
+        match &self.attributes {
+            Some(x) => match x.column(name) {
+                Some(c) => Ok(c),
+                None => Err(StartinError::WrongAttribute),
+            },
+            None => Err(StartinError::TinHasNoAttributes),
+        }
+    }
+
+    /// Returns all the columns of the attributes, in the order of the schema.
+    pub fn attribute_columns(&self) -> Result<&[AttributeColumn], StartinError> {
+        match &self.attributes {
+            Some(x) => Ok(x.columns()),
+            None => Err(StartinError::TinHasNoAttributes),
+        }
+    }
+
+    /// Sets all the values of the attribute `name` at once.
+    /// `values` must have one value per vertex (including the infinite one and
+    /// the removed ones, which are ignored) and be of the type of the schema,
+    /// otherwise a [`StartinError::WrongAttribute`] is returned.
+    pub fn set_attribute_column(
+        &mut self,
+        name: &str,
+        values: ColumnValues,
+    ) -> Result<(), StartinError> {
+        let mut unset: Vec<usize> = self.removed_indices.clone();
+        unset.push(0);
+        match &mut self.attributes {
+            Some(x) => x.set_column(name, values, &unset),
+            None => Err(StartinError::TinHasNoAttributes),
+        }
+    }
+
+    /// Configure the extra attributes that each (finite) triangle can store,
+    /// same as [`Triangulation::set_attributes_schema()`] for the vertices.
+    pub fn set_triangle_attributes_schema(
+        &mut self,
+        att_schema: Vec<(String, String)>,
+    ) -> Result<(), StartinError> {
+        let schema = parse_schema(&att_schema)?;
+        self.triangle_attributes.set_schema(&schema)
+    }
+
+    pub fn get_triangle_attributes_schema(&self) -> Vec<(String, String)> {
+        self.triangle_attributes
+            .schema()
+            .iter()
+            .map(|(name, dtype)| (name.clone(), dtype.to_string()))
+            .collect()
+    }
+
+    /// Returns the value of the attribute `name` of the triangle `tr`,
+    /// `None` if no value was set for that triangle.
+    pub fn get_triangle_attribute(
+        &self,
+        tr: &Triangle,
+        name: &str,
+    ) -> Result<Option<AttributeValue>, StartinError> {
+        if !self.is_triangle(tr) || tr.is_infinite() {
+            return Err(StartinError::TriangleNotPresent);
+        }
+        if !self.triangle_attributes.has_schema() {
+            return Err(StartinError::TinHasNoAttributes);
+        }
+        self.triangle_attributes.get(&triangle_key(&tr.v), name)
+    }
+
+    /// Sets the value of the attribute `name` of the triangle `tr`.
+    /// The value is kept when the triangle is modified by a flip, according to the
+    /// [`InheritanceRules`] (see [`Triangulation::set_attributes_inheritance()`]).
+    pub fn set_triangle_attribute(
+        &mut self,
+        tr: &Triangle,
+        name: &str,
+        value: AttributeValue,
+    ) -> Result<(), StartinError> {
+        if !self.is_triangle(tr) || tr.is_infinite() {
+            return Err(StartinError::TriangleNotPresent);
+        }
+        if !self.triangle_attributes.has_schema() {
+            return Err(StartinError::TinHasNoAttributes);
+        }
+        self.triangle_attributes
+            .set(triangle_key(&tr.v), name, value)
+    }
+
+    /// Returns the extra attributes of the triangle `tr` as a JSON object
+    /// (only the attributes that were set are present).
+    pub fn get_triangle_attributes(&self, tr: &Triangle) -> Result<Value, StartinError> {
+        if !self.is_triangle(tr) || tr.is_infinite() {
+            return Err(StartinError::TriangleNotPresent);
+        }
+        if !self.triangle_attributes.has_schema() {
+            return Err(StartinError::TinHasNoAttributes);
+        }
+        Ok(self.triangle_attributes.get_json(&triangle_key(&tr.v)))
+    }
+
+    /// Configure the extra attributes that each (finite) edge can store,
+    /// same as [`Triangulation::set_attributes_schema()`] for the vertices.
+    pub fn set_edge_attributes_schema(
+        &mut self,
+        att_schema: Vec<(String, String)>,
+    ) -> Result<(), StartinError> {
+        let schema = parse_schema(&att_schema)?;
+        self.edge_attributes.set_schema(&schema)
+    }
+
+    pub fn get_edge_attributes_schema(&self) -> Vec<(String, String)> {
+        self.edge_attributes
+            .schema()
+            .iter()
+            .map(|(name, dtype)| (name.clone(), dtype.to_string()))
+            .collect()
+    }
+
+    /// Returns the value of the attribute `name` of the edge `va`-`vb`,
+    /// `None` if no value was set for that edge.
+    pub fn get_edge_attribute(
+        &self,
+        va: usize,
+        vb: usize,
+        name: &str,
+    ) -> Result<Option<AttributeValue>, StartinError> {
+        if !self.is_finite_edge(va, vb) {
+            return Err(StartinError::EdgeNotPresent);
+        }
+        if !self.edge_attributes.has_schema() {
+            return Err(StartinError::TinHasNoAttributes);
+        }
+        self.edge_attributes.get(&edge_key(va, vb), name)
+    }
+
+    /// Sets the value of the attribute `name` of the edge `va`-`vb`.
+    /// If the edge is later split by the insertion of a vertex, its 2 halves keep the value
+    /// (unless deactivated with [`Triangulation::set_attributes_inheritance()`]); if the edge
+    /// is flipped or one of its vertices is removed then the value is dropped.
+    pub fn set_edge_attribute(
+        &mut self,
+        va: usize,
+        vb: usize,
+        name: &str,
+        value: AttributeValue,
+    ) -> Result<(), StartinError> {
+        if !self.is_finite_edge(va, vb) {
+            return Err(StartinError::EdgeNotPresent);
+        }
+        if !self.edge_attributes.has_schema() {
+            return Err(StartinError::TinHasNoAttributes);
+        }
+        self.edge_attributes.set(edge_key(va, vb), name, value)
+    }
+
+    /// Returns the extra attributes of the edge `va`-`vb` as a JSON object
+    /// (only the attributes that were set are present).
+    pub fn get_edge_attributes(&self, va: usize, vb: usize) -> Result<Value, StartinError> {
+        if !self.is_finite_edge(va, vb) {
+            return Err(StartinError::EdgeNotPresent);
+        }
+        if !self.edge_attributes.has_schema() {
+            return Err(StartinError::TinHasNoAttributes);
+        }
+        Ok(self.edge_attributes.get_json(&edge_key(va, vb)))
+    }
+
+    /// Set the rules defining what happens to the attributes of the triangles and
+    /// edges when these are modified by flips (see [`InheritanceRules`] for the default).
+    pub fn set_attributes_inheritance(&mut self, rules: InheritanceRules) {
+        self.inheritance = rules;
+    }
+
+    pub fn get_attributes_inheritance(&self) -> InheritanceRules {
+        self.inheritance
     }
 
     /// Returns the 3 adjacents (finite + infinite) [`Triangle`] to a triangle.
@@ -1065,6 +1415,14 @@ impl Triangulation {
         }
     }
 
+    /// Returns whether `va`-`vb` is a finite edge of the triangulation.
+    pub fn is_finite_edge(&self, va: usize, vb: usize) -> bool {
+        if va == 0 || vb == 0 || !self.is_vertex_valid(va) || !self.is_vertex_valid(vb) {
+            return false;
+        }
+        self.stars[va].link.get_index(vb).is_some()
+    }
+
     /// Returns whether a [`Triangle`] is finite, or not
     pub fn is_finite(&self, tr: &Triangle) -> bool {
         return !tr.is_infinite();
@@ -1163,29 +1521,56 @@ impl Triangulation {
     /// Returns, if it exists, the [`Triangle`] containing `(px, py)`.
     /// If it is direction on a vertex/edge, then one is randomly chosen.
     pub fn locate(&mut self, px: f64, py: f64) -> Result<Triangle, StartinError> {
+        let re = self.locate_from(px, py, None);
+        if let Ok(tr) = &re {
+            self.cur = tr.v[0];
+        }
+        re
+    }
+
+    /// Returns, if it exists, the [`Triangle`] containing `(px, py)`, without
+    /// modifying the triangulation (it can thus be shared between threads).
+    /// The walk starts at the vertex `start` (eg one returned by a previous
+    /// query close to `(px, py)`), or at the last inserted/located vertex if `None`.
+    pub fn locate_from(
+        &self,
+        px: f64,
+        py: f64,
+        start: Option<usize>,
+    ) -> Result<Triangle, StartinError> {
         if !self.is_init {
             return Err(StartinError::EmptyTriangulation);
         }
         let p: [f64; 3] = [px, py, 0.0];
-        let re = self.walk(&p);
+        let re = self.walk_from(&p, self.start_vertex(start));
         match re.is_infinite() {
             true => Err(StartinError::OutsideConvexHull),
-            false => {
-                self.cur = re.v[0];
-                return Ok(re);
-            }
+            false => Ok(re),
         }
     }
 
     /// Returns closest point (in 2D) to a query point `(x, y)`.
     /// if `(px, py)` is outside the convex hull then [`StartinError::OutsideConvexHull`] is raised.
     pub fn closest_point(&mut self, px: f64, py: f64) -> Result<usize, StartinError> {
-        let re = self.locate(px, py);
-        if re.is_err() {
-            return Err(re.err().unwrap());
-        }
+        let tr = self.locate(px, py)?;
+        Ok(self.closest_point_in_star(&tr, px, py))
+    }
+
+    /// Returns closest point (in 2D) to a query point `(x, y)`, without modifying
+    /// the triangulation (see [`Triangulation::locate_from()`] for `start`).
+    /// if `(px, py)` is outside the convex hull then [`StartinError::OutsideConvexHull`] is raised.
+    pub fn closest_point_from(
+        &self,
+        px: f64,
+        py: f64,
+        start: Option<usize>,
+    ) -> Result<usize, StartinError> {
+        let tr = self.locate_from(px, py, start)?;
+        Ok(self.closest_point_in_star(&tr, px, py))
+    }
+
+    fn closest_point_in_star(&self, tr: &Triangle, px: f64, py: f64) -> usize {
         let p: [f64; 3] = [px, py, 0.0];
-        let tr = re.unwrap();
         let mut d = std::f64::MAX;
         let mut closest: usize = 0;
         //-- find closest vertex in the triangle containing p
@@ -1211,16 +1596,35 @@ impl Triangulation {
                 break;
             }
         }
-        Ok(closest)
+        closest
+    }
+
+    /// The vertex where a walk starts: `start` if it is a valid finite vertex,
+    /// otherwise the last inserted/located one
+    fn start_vertex(&self, start: Option<usize>) -> usize {
+        match start {
+            Some(vi) if vi != 0 && self.is_vertex_valid(vi) => vi,
+            _ => self.cur,
+        }
+    }
+
+    /// The spatial index of the (finite and not removed) vertices, built the first
+    /// time it is needed and kept until vertices are inserted/removed
+    fn vertex_index(&self) -> &VertexIndex {
+        self.vertex_index.get_or_init(|| VertexIndex::new(self))
     }
 
     fn walk(&self, x: &[f64]) -> Triangle {
+        self.walk_from(x, self.cur)
+    }
+
+    fn walk_from(&self, x: &[f64], start: usize) -> Triangle {
         //-- find the starting tr
-        let mut cur = self.cur;
+        let mut cur = start;
         //-- jump-and-walk
         if self.jump_and_walk {
             let mut rng = thread_rng();
-            let mut d: f64 = geom::distance2d_squared(&self.stars[self.cur].pt, x);
+            let mut d: f64 = geom::distance2d_squared(&self.stars[start].pt, x);
             let n = (self.stars.len() as f64).powf(0.25);
             // let n = (self.stars.len() as f64).powf(0.25) * 7.0;
             for _i in 0..n as i32 {
@@ -1323,6 +1727,12 @@ impl Triangulation {
         self.stars[opposite].link.insert_after_v(tr.v[0], tr.v[2]);
         //-- step 4.
         self.stars[tr.v[2]].link.delete(tr.v[1]);
+        if !self.triangle_attributes.is_empty() {
+            self.flip22_triangle_attributes(tr, opposite);
+        }
+        if !self.edge_attributes.is_empty() {
+            self.flip22_edge_attributes(tr, opposite);
+        }
         //-- make 2 triangles to return (to stack)
         let ret0 = Triangle {
             v: [tr.v[0], tr.v[1], opposite],
@@ -1333,6 +1743,97 @@ impl Triangulation {
         (ret0, ret1)
     }
 
+    /// The triangles (v0, v1, v2) and (v1, opposite, v2) are replaced by
+    /// (v0, v1, opposite) and (v0, opposite, v2)
+    fn flip22_triangle_attributes(&mut self, tr: &Triangle, opposite: usize) {
+        let old0 = [tr.v[0], tr.v[1], tr.v[2]];
+        let old1 = [tr.v[1], opposite, tr.v[2]];
+        let row0 = self.triangle_attributes.take(&triangle_key(&old0));
+        let row1 = self.triangle_attributes.take(&triangle_key(&old1));
+        if row0.is_none() && row1.is_none() {
+            return;
+        }
+        let row = match self.inheritance.flip22 {
+            TriangleInheritance::Drop => None,
+            TriangleInheritance::IfEqual => match row0 == row1 {
+                true => row0,
+                false => None,
+            },
+            TriangleInheritance::LargestOverlap => {
+                //-- both new triangles overlap most the old triangle whose apex
+                //-- is the furthest from the flipped edge, ie the largest one
+                let a0 = match old0.contains(&0) {
+                    true => 0.0,
+                    false => geom::area2d_triangle(
+                        &self.stars[old0[0]].pt,
+                        &self.stars[old0[1]].pt,
+                        &self.stars[old0[2]].pt,
+                    )
+                    .abs(),
+                };
+                let a1 = match old1.contains(&0) {
+                    true => 0.0,
+                    false => geom::area2d_triangle(
+                        &self.stars[old1[0]].pt,
+                        &self.stars[old1[1]].pt,
+                        &self.stars[old1[2]].pt,
+                    )
+                    .abs(),
+                };
+                match a0 >= a1 {
+                    true => row0,
+                    false => row1,
+                }
+            }
+        };
+        if let Some(row) = row {
+            for t in [[tr.v[0], tr.v[1], opposite], [tr.v[0], opposite, tr.v[2]]] {
+                if !t.contains(&0) {
+                    self.triangle_attributes
+                        .insert_row(triangle_key(&t), row.clone());
+                }
+            }
+        }
+    }
+
+    /// The edge (v1, v2) is replaced by (v0, opposite); if one of these 2 vertices
+    /// is on the edge then the edge was split and its 2 halves can inherit its attributes
+    fn flip22_edge_attributes(&mut self, tr: &Triangle, opposite: usize) {
+        let row = match self.edge_attributes.take(&edge_key(tr.v[1], tr.v[2])) {
+            Some(x) => x,
+            None => return,
+        };
+        if !self.inheritance.split_edges || tr.v[1] == 0 || tr.v[2] == 0 {
+            return;
+        }
+        for v in [tr.v[0], opposite] {
+            if v != 0
+                && geom::orient2d(
+                    &self.stars[tr.v[1]].pt,
+                    &self.stars[tr.v[2]].pt,
+                    &self.stars[v].pt,
+                    self.robust_predicates,
+                ) == 0
+            {
+                self.edge_attributes
+                    .insert_row(edge_key(v, tr.v[1]), row.clone());
+                self.edge_attributes.insert_row(edge_key(v, tr.v[2]), row);
+                return;
+            }
+        }
+    }
+
+    /// Drops the attributes of the triangles and edges incident to `vi`
+    fn drop_incident_attributes(&mut self, vi: usize) {
+        let l = &self.stars[vi].link;
+        for (i, v) in l.iter().enumerate() {
+            let _ = self
+                .triangle_attributes
+                .take(&triangle_key(&[vi, *v, l[l.next_index(i)]]));
+            let _ = self.edge_attributes.take(&edge_key(vi, *v));
+        }
+    }
+
     fn get_opposite_vertex(&self, tr: &Triangle) -> usize {
         self.stars[tr.v[2]].link.get_next_vertex(tr.v[1]).unwrap()
     }
@@ -1529,6 +2030,8 @@ impl Triangulation {
                 for i in 0..self.stars.len() {
                     self.stars[i].link.clear();
                 }
+                self.triangle_attributes.clear();
+                self.edge_attributes.clear();
                 self.stars[v].pt[0] = f64::NAN;
                 self.stars[v].pt[1] = f64::NAN;
                 self.stars[v].pt[2] = f64::NAN;
@@ -1549,6 +2052,8 @@ impl Triangulation {
                     for i in 0..self.stars.len() {
                         self.stars[i].link.clear();
                     }
+                    self.triangle_attributes.clear();
+                    self.edge_attributes.clear();
                     self.stars[v].pt[0] = f64::NAN;
                     self.stars[v].pt[1] = f64::NAN;
                     self.stars[v].pt[2] = f64::NAN;
@@ -1591,6 +2096,7 @@ impl Triangulation {
         if vi == 0 {
             return Err(StartinError::VertexInfinite);
         }
+        self.vertex_index.take();
         if !self.is_init {
             self.stars[vi].pt[0] = f64::NAN;
             self.stars[vi].pt[1] = f64::NAN;
@@ -1608,6 +2114,7 @@ impl Triangulation {
                 }
             }
         }
+        self.drop_incident_attributes(vi);
         if self.is_vertex_convex_hull(vi) {
             return self.remove_on_convex_hull(vi);
         }
@@ -1719,26 +2226,48 @@ impl Triangulation {
         writeln!(f, "property double x").unwrap();
         writeln!(f, "property double y").unwrap();
         writeln!(f, "property double z").unwrap();
-        for each in &self.attributes_schema {
-            // println!("{:?}", each);
-            match each.1.as_ref() {
-                "f64" => {
-                    writeln!(f, "property double {}", each.0).unwrap();
+        let columns: &[AttributeColumn] = match &self.attributes {
+            Some(x) => x.columns(),
+            None => &[],
+        };
+        for each in columns {
+            match each.dtype() {
+                AttributeType::F64 => {
+                    writeln!(f, "property double {}", each.name()).unwrap();
                 }
-                "i64" => {
-                    writeln!(f, "property int {}", each.0).unwrap();
+                AttributeType::I64 => {
+                    writeln!(f, "property int {}", each.name()).unwrap();
                 }
-                "u64" => {
-                    writeln!(f, "property uint {}", each.0).unwrap();
+                AttributeType::U64 => {
+                    writeln!(f, "property uint {}", each.name()).unwrap();
                 }
-                "bool" => {
-                    writeln!(f, "property uint {}", each.0).unwrap();
+                AttributeType::Bool => {
+                    writeln!(f, "property uint {}", each.name()).unwrap();
                 }
-                _ => (),
+                AttributeType::String => (),
             }
         }
         writeln!(f, "element face {}", trs.len()).unwrap();
         writeln!(f, "property list uchar int vertex_indices").unwrap();
+        for (name, dtype) in self.triangle_attributes.schema() {
+            if let Some(t) = ply_type(*dtype) {
+                writeln!(f, "property {} {}", t, name).unwrap();
+            }
+        }
+        let edges = match self.edge_attributes.has_schema() {
+            true => self.all_finite_edges(),
+            false => Vec::new(),
+        };
+        if self.edge_attributes.has_schema() {
+            writeln!(f, "element edge {}", edges.len() / 2).unwrap();
+            writeln!(f, "property int vertex1").unwrap();
+            writeln!(f, "property int vertex2").unwrap();
+            for (name, dtype) in self.edge_attributes.schema() {
+                if let Some(t) = ply_type(*dtype) {
+                    writeln!(f, "property {} {}", t, name).unwrap();
+                }
+            }
+        }
         writeln!(f, "end_header").unwrap();
         //-- find one good vertice to replace the '[nan, nan, nan]' of the deleted ones
         let mut onegoodpt = [1.0, 1.0, 1.0];
@@ -1764,25 +2293,19 @@ impl Triangulation {
                 ));
             }
             //-- extra attributes
-            for each in &self.attributes_schema {
-                match each.1.as_ref() {
-                    "f64" | "i64" | "u64" => {
-                        let a = self.attributes.as_ref().unwrap();
-                        let b = &a[i];
-                        let c = &b[&each.0];
-                        s.push_str(&format!(" {}", c));
-                    }
-                    "bool" => {
-                        let a = self.attributes.as_ref().unwrap();
-                        let b = &a[i];
-                        let c = &b[&each.0];
-                        if c == true {
+            for each in columns {
+                match each.values() {
+                    ColumnValues::F64(c) => s.push_str(&format!(" {}", c[i])),
+                    ColumnValues::I64(c) => s.push_str(&format!(" {}", c[i])),
+                    ColumnValues::U64(c) => s.push_str(&format!(" {}", c[i])),
+                    ColumnValues::Bool(c) => {
+                        if c[i] {
                             s.push_str(" 1");
                         } else {
                             s.push_str(" 0");
                         }
                     }
-                    _ => (),
+                    ColumnValues::String(_) => (),
                 }
             }
             s.push_str("\n");
@@ -1791,11 +2314,30 @@ impl Triangulation {
         let mut s = String::new();
         for tr in trs.iter() {
             s.push_str(&format!(
-                "3 {} {} {}\n",
+                "3 {} {} {}",
                 tr.v[0] - 1,
                 tr.v[1] - 1,
                 tr.v[2] - 1
             ));
+            //-- triangle attributes
+            let row = self.triangle_attributes.row(&triangle_key(&tr.v));
+            for (i, (_name, dtype)) in self.triangle_attributes.schema().iter().enumerate() {
+                if *dtype != AttributeType::String {
+                    s.push_str(&ply_value(row.and_then(|r| r[i].as_ref())));
+                }
+            }
+            s.push('\n');
+        }
+        for e in edges.chunks(2) {
+            s.push_str(&format!("{} {}", e[0] - 1, e[1] - 1));
+            //-- edge attributes
+            let row = self.edge_attributes.row(&edge_key(e[0], e[1]));
+            for (i, (_name, dtype)) in self.edge_attributes.schema().iter().enumerate() {
+                if *dtype != AttributeType::String {
+                    s.push_str(&ply_value(row.and_then(|r| r[i].as_ref())));
+                }
+            }
+            s.push('\n');
         }
         write!(f, "{}", s).unwrap();
         Ok(())
@@ -1946,6 +2488,7 @@ impl Triangulation {
     /// Watch out: the vertices get new IDs (and thus the triangles) too. And this can
     /// be a slow operation.
     pub fn collect_garbage(&mut self) {
+        self.vertex_index.take();
         self.removed_indices.sort_unstable();
         for star in self.stars.iter_mut() {
             for value in star.link.0.iter_mut() {
@@ -1954,13 +2497,16 @@ impl Triangulation {
                 *value = newv;
             }
         }
+        let removed = &self.removed_indices;
+        let newid = |v: usize| v - removed.binary_search(&v).unwrap_err();
+        self.triangle_attributes
+            .remap(|k| [newid(k[0]), newid(k[1]), newid(k[2])]);
+        self.edge_attributes.remap(|k| [newid(k[0]), newid(k[1])]);
         let mut offset = 0;
         for each in &self.removed_indices {
             self.stars.remove(each - offset);
             match &mut self.attributes {
-                Some(x) => {
-                    let _ = x.remove(each - offset);
-                }
+                Some(x) => x.remove_row(each - offset),
                 None => (),
             };
             offset += 1;
@@ -1968,6 +2514,26 @@ impl Triangulation {
         self.removed_indices.clear();
         self.cur = 1;
     }
+
+    /// A copy of the triangulation without the attributes (of the vertices, triangles and edges),
+    /// the vertices keep their IDs.
+    fn copy_geometry(&self) -> Triangulation {
+        Triangulation {
+            stars: self.stars.clone(),
+            attributes: None,
+            triangle_attributes: KeyedAttributes::new(),
+            edge_attributes: KeyedAttributes::new(),
+            inheritance: self.inheritance,
+            snaptol: self.snaptol,
+            cur: self.cur,
+            is_init: self.is_init,
+            jump_and_walk: self.jump_and_walk,
+            robust_predicates: self.robust_predicates,
+            removed_indices: self.removed_indices.clone(),
+            duplicates_handling: DuplicateHandling::First,
+            vertex_index: OnceLock::new(),
+        }
+    }
 }
 
 impl fmt::Display for Triangulation {
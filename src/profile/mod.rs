@@ -0,0 +1,265 @@
+//! # profile
+//!
+//! Elevation profiles (cross-sections) along a polyline: the polyline is followed through
+//! the triangulation and all its intersections with the edges and the vertices are returned,
+//! so the profile is exact for the TIN (it is not sampled).
+
+use crate::StartinError;
+use crate::Triangulation;
+
+use crate::geom;
+
+/// A point of a profile, `distance` is the distance (in 2D) from the start of the polyline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfilePoint {
+    pub distance: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// the vertex of the triangulation if the point is one
+    pub vertex: Option<usize>,
+}
+
+/// Returns the profile of the TIN along the polyline: its points are the vertices of the
+/// polyline and all the intersections of the polyline with the edges and vertices of the
+/// triangulation, in order.
+/// Returns [`StartinError::OutsideConvexHull`] if a vertex of the polyline is outside the
+/// convex hull, and [`StartinError::EmptyTriangulation`] if the triangulation is not initialised.
+pub fn profile(
+    dt: &Triangulation,
+    polyline: &[[f64; 2]],
+) -> Result<Vec<ProfilePoint>, StartinError> {
+    let mut re: Vec<ProfilePoint> = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut dist = 0.0;
+    if polyline.len() == 1 {
+        let c = trace_segment(dt, &polyline[0], &polyline[0], &mut start, false)?;
+        return Ok(vec![c[0].to_profile_point(0.0)]);
+    }
+    for w in polyline.windows(2) {
+        let len = ((w[1][0] - w[0][0]).powi(2) + (w[1][1] - w[0][1]).powi(2)).sqrt();
+        let cs = trace_segment(dt, &w[0], &w[1], &mut start, false)?;
+        //-- the first point is the last one of the previous segment
+        let skip = if re.is_empty() { 0 } else { 1 };
+        for c in cs.iter().skip(skip) {
+            re.push(c.to_profile_point(dist + c.t * len));
+        }
+        dist += len;
+    }
+    Ok(re)
+}
+
+/// An intersection of a segment with the triangulation, `t` is its parameter along the
+/// segment (0.0 at the start and 1.0 at the end)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crossing {
+    pub t: f64,
+    pub pt: [f64; 3],
+    pub vertex: Option<usize>,
+    /// a triangle containing the part of the segment that ends at this crossing
+    /// (for the start of the segment, the triangle containing it)
+    pub triangle: [usize; 3],
+}
+
+impl Crossing {
+    fn to_profile_point(self, distance: f64) -> ProfilePoint {
+        ProfilePoint {
+            distance,
+            x: self.pt[0],
+            y: self.pt[1],
+            z: self.pt[2],
+            vertex: self.vertex,
+        }
+    }
+}
+
+/// Where the walk along the segment is: inside a triangle (CCW), or at a vertex
+enum State {
+    Triangle([usize; 3]),
+    Vertex(usize),
+}
+
+/// Follows the segment `p`-`q` through the triangulation (from the triangle containing `p`),
+/// and returns all its intersections with the edges and the vertices, including `p` and `q`.
+/// The side of the vertices with respect to the line `pq` is always computed with the
+/// predicates of the triangulation, so the decisions are consistent.
+/// `start` is the vertex where the walk to locate `p` starts, it is updated for the next segment.
+/// If `clip` is true then `q` can be outside the convex hull, and the segment stops where it
+/// leaves the triangulation (otherwise [`StartinError::OutsideConvexHull`] is returned).
+pub(crate) fn trace_segment(
+    dt: &Triangulation,
+    p: &[f64; 2],
+    q: &[f64; 2],
+    start: &mut Option<usize>,
+    clip: bool,
+) -> Result<Vec<Crossing>, StartinError> {
+    let tr = dt.locate_from(p[0], p[1], *start)?;
+    *start = Some(tr.v[0]);
+    if !clip {
+        //-- the triangulation is convex, so the segment is inside if q is
+        let trq = dt.locate_from(q[0], q[1], Some(tr.v[0]))?;
+        *start = Some(trq.v[0]);
+    }
+    let rp = dt.robust_predicates;
+    let pt = |v: usize| &dt.stars[v].pt;
+    let pp: [f64; 3] = [p[0], p[1], 0.0];
+    let qq: [f64; 3] = [q[0], q[1], 0.0];
+    let d = [q[0] - p[0], q[1] - p[1]];
+    let len2 = d[0] * d[0] + d[1] * d[1];
+    let param = |x: &[f64]| ((x[0] - p[0]) * d[0] + (x[1] - p[1]) * d[1]) / len2;
+    let at = |v: usize, x: &[f64; 2]| pt(v)[0] == x[0] && pt(v)[1] == x[1];
+
+    let mut re: Vec<Crossing> = Vec::new();
+    let mut state = match tr.v.iter().find(|v| at(**v, p)) {
+        Some(v) => {
+            re.push(Crossing {
+                t: 0.0,
+                pt: *pt(*v),
+                vertex: Some(*v),
+                triangle: tr.v,
+            });
+            State::Vertex(*v)
+        }
+        None => {
+            re.push(Crossing {
+                t: 0.0,
+                pt: [p[0], p[1], z_in_triangle(dt, &tr.v, p)],
+                vertex: None,
+                triangle: tr.v,
+            });
+            State::Triangle(tr.v)
+        }
+    };
+    if len2 == 0.0 {
+        return Ok(re);
+    }
+    let mut tcur = 0.0;
+    loop {
+        match state {
+            State::Triangle(t) => {
+                if (0..3).all(|i| geom::orient2d(pt(t[i]), pt(t[(i + 1) % 3]), &qq, rp) >= 0) {
+                    let vertex = t.iter().find(|v| at(**v, q)).copied();
+                    re.push(Crossing {
+                        t: 1.0,
+                        pt: match vertex {
+                            Some(v) => *pt(v),
+                            None => [q[0], q[1], z_in_triangle(dt, &t, q)],
+                        },
+                        vertex,
+                        triangle: t,
+                    });
+                    break;
+                }
+                let s: Vec<i8> = t
+                    .iter()
+                    .map(|v| geom::orient2d(&pp, &qq, pt(*v), rp))
+                    .collect();
+                //-- the segment leaves the triangle through a vertex
+                if let Some(i) = (0..3).find(|i| s[*i] == 0 && param(pt(t[*i])) > tcur) {
+                    tcur = param(pt(t[i]));
+                    re.push(Crossing {
+                        t: tcur,
+                        pt: *pt(t[i]),
+                        vertex: Some(t[i]),
+                        triangle: t,
+                    });
+                    state = State::Vertex(t[i]);
+                    continue;
+                }
+                //-- or through the edge u->w (u on the right of pq, w on the left)
+                let i = (0..3)
+                    .find(|i| s[*i] < 0 && s[(i + 1) % 3] > 0)
+                    .ok_or(StartinError::OutsideConvexHull)?;
+                let (u, w) = (t[i], t[(i + 1) % 3]);
+                let su = d[0] * (pt(u)[1] - p[1]) - d[1] * (pt(u)[0] - p[0]);
+                let sw = d[0] * (pt(w)[1] - p[1]) - d[1] * (pt(w)[0] - p[0]);
+                let r = su / (su - sw);
+                let x: [f64; 3] = [
+                    pt(u)[0] + r * (pt(w)[0] - pt(u)[0]),
+                    pt(u)[1] + r * (pt(w)[1] - pt(u)[1]),
+                    pt(u)[2] + r * (pt(w)[2] - pt(u)[2]),
+                ];
+                tcur = param(&x);
+                if re.last().unwrap().pt != x {
+                    re.push(Crossing {
+                        t: tcur,
+                        pt: x,
+                        vertex: None,
+                        triangle: t,
+                    });
+                }
+                let n = dt.stars[w].link.get_next_vertex(u).unwrap();
+                if n == 0 {
+                    if clip {
+                        break;
+                    }
+                    return Err(StartinError::OutsideConvexHull);
+                }
+                state = State::Triangle([w, u, n]);
+            }
+            State::Vertex(v) => {
+                if at(v, q) {
+                    re.last_mut().unwrap().t = 1.0;
+                    break;
+                }
+                let l = &dt.stars[v].link;
+                let mut next: Option<State> = None;
+                for (i, a) in l.iter().enumerate() {
+                    let b = l[l.next_index(i)];
+                    if *a == 0 {
+                        continue;
+                    }
+                    let oa = geom::orient2d(pt(v), pt(*a), &qq, rp);
+                    //-- the segment follows the edge v->a
+                    if oa == 0 && param(pt(*a)) > tcur {
+                        let ta = param(pt(*a));
+                        let triangle = if b != 0 {
+                            [v, *a, b]
+                        } else {
+                            [*a, v, dt.stars[*a].link.get_next_vertex(v).unwrap()]
+                        };
+                        if ta >= 1.0 {
+                            let r = (1.0 - tcur) / (ta - tcur);
+                            re.push(Crossing {
+                                t: 1.0,
+                                pt: [q[0], q[1], pt(v)[2] + r * (pt(*a)[2] - pt(v)[2])],
+                                vertex: if at(*a, q) { Some(*a) } else { None },
+                                triangle,
+                            });
+                            return Ok(re);
+                        }
+                        tcur = ta;
+                        re.push(Crossing {
+                            t: tcur,
+                            pt: *pt(*a),
+                            vertex: Some(*a),
+                            triangle,
+                        });
+                        next = Some(State::Vertex(*a));
+                        break;
+                    }
+                    if b != 0 && oa > 0 && geom::orient2d(pt(v), pt(b), &qq, rp) < 0 {
+                        next = Some(State::Triangle([v, *a, b]));
+                        break;
+                    }
+                }
+                state = match next {
+                    Some(n) => n,
+                    None if clip => break,
+                    None => return Err(StartinError::OutsideConvexHull),
+                };
+            }
+        }
+    }
+    Ok(re)
+}
+
+/// The z-value at `p` of the plane of the triangle
+pub(crate) fn z_in_triangle(dt: &Triangulation, t: &[usize; 3], p: &[f64; 2]) -> f64 {
+    geom::z_on_plane(
+        &dt.stars[t[0]].pt,
+        &dt.stars[t[1]].pt,
+        &dt.stars[t[2]].pt,
+        p,
+    )
+}
@@ -0,0 +1,191 @@
+//! # smoothing
+//!
+//! Smoothing of the z-values of the vertices of a triangulation (eg to remove the noise of
+//! a photogrammetric point cloud), the xy-coordinates are not modified and thus the
+//! triangulation stays the same.
+//! The neighbourhood of a vertex is formed by its adjacent vertices, and at each iteration
+//! all the vertices are updated at once (with the z-values of the previous iteration).
+
+use crate::attributes::AttributeValue;
+use crate::StartinError;
+use crate::Triangulation;
+
+use crate::geom;
+use crate::triangle_key;
+
+use std::collections::HashMap;
+
+/// The smoothing operator applied at each iteration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmoothingMethod {
+    /// the z-value moves towards the average of the adjacent vertices by the factor
+    /// `lambda` (0.0 < `lambda` <= 1.0)
+    Laplacian { lambda: f64 },
+    /// Taubin's lambda|mu smoothing: a Laplacian step with `lambda` followed by
+    /// one with `mu` (negative and with `-mu` > `lambda`), this avoids the shrinkage
+    /// of the Laplacian smoothing (peaks and valleys are not flattened as much)
+    Taubin { lambda: f64, mu: f64 },
+    /// Feature-preserving bilateral normal filtering (Zheng et al., 2011): the normals of
+    /// the triangles are first smoothed with those of the adjacent triangles, weighted by
+    /// gaussians of the distance between their centroids (`sigma_distance`) and of the
+    /// difference between the (unit) normals (`sigma_normal`), so that the triangles on the
+    /// other side of a breakline have little influence.
+    /// The z-values are then moved towards the planes of the incident triangles.
+    Bilateral {
+        sigma_distance: f64,
+        sigma_normal: f64,
+    },
+}
+
+/// The parameters of [`smooth_z()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Smoothing {
+    pub method: SmoothingMethod,
+    pub iterations: usize,
+    /// the vertices on the convex hull keep their z-values
+    pub lock_convex_hull: bool,
+    /// the vertices having this value for this attribute keep their z-values
+    pub lock_attribute: Option<(String, AttributeValue)>,
+}
+
+/// Smooths in place the z-values of the vertices of the triangulation.
+/// Returns the number of vertices that were smoothed (those not locked).
+/// Returns [`StartinError::EmptyTriangulation`] if the triangulation is not initialised,
+/// and [`StartinError::WrongAttribute`] if the attribute to lock is not in the schema.
+pub fn smooth_z(dt: &mut Triangulation, smoothing: &Smoothing) -> Result<usize, StartinError> {
+    if !dt.is_init {
+        return Err(StartinError::EmptyTriangulation);
+    }
+    let mut free: Vec<usize> = (1..dt.stars.len())
+        .filter(|vi| !dt.stars[*vi].is_deleted())
+        .collect();
+    if smoothing.lock_convex_hull {
+        free.retain(|vi| !dt.is_vertex_convex_hull(*vi));
+    }
+    if let Some((name, value)) = &smoothing.lock_attribute {
+        let c = dt.attribute_column(name)?;
+        free.retain(|vi| c.get(*vi).as_ref() != Some(value));
+    }
+    for _ in 0..smoothing.iterations {
+        match smoothing.method {
+            SmoothingMethod::Laplacian { lambda } => laplacian_step(dt, &free, lambda),
+            SmoothingMethod::Taubin { lambda, mu } => {
+                laplacian_step(dt, &free, lambda);
+                laplacian_step(dt, &free, mu);
+            }
+            SmoothingMethod::Bilateral {
+                sigma_distance,
+                sigma_normal,
+            } => bilateral_step(dt, &free, sigma_distance, sigma_normal),
+        }
+    }
+    Ok(free.len())
+}
+
+fn laplacian_step(dt: &mut Triangulation, free: &[usize], factor: f64) {
+    let newzs: Vec<f64> = free
+        .iter()
+        .map(|vi| {
+            let z = dt.stars[*vi].pt[2];
+            let (sum, n) = dt.stars[*vi]
+                .link
+                .iter()
+                .filter(|v| **v != 0)
+                .fold((0.0, 0), |(s, n), v| (s + dt.stars[*v].pt[2], n + 1));
+            z + factor * (sum / n as f64 - z)
+        })
+        .collect();
+    for (vi, z) in free.iter().zip(newzs) {
+        dt.stars[*vi].pt[2] = z;
+    }
+}
+
+/// A finite triangle: its unit normal, its centroid, and its area
+struct Face {
+    v: [usize; 3],
+    n: [f64; 3],
+    c: [f64; 3],
+    area: f64,
+}
+
+fn bilateral_step(dt: &mut Triangulation, free: &[usize], sigma_distance: f64, sigma_normal: f64) {
+    //-- the finite triangles, each is stored once with its key (smallest vertex first)
+    let mut faces: Vec<Face> = Vec::new();
+    let mut index: HashMap<[usize; 3], usize> = HashMap::new();
+    for vi in 1..dt.stars.len() {
+        let l = &dt.stars[vi].link;
+        for (i, v) in l.iter().enumerate() {
+            let w = l[l.next_index(i)];
+            if *v == 0 || w == 0 || *v < vi || w < vi {
+                continue;
+            }
+            let (a, b, c) = (&dt.stars[vi].pt, &dt.stars[*v].pt, &dt.stars[w].pt);
+            let n = geom::normal_triangle(a, b, c, false);
+            let norm = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            index.insert([vi, *v, w], faces.len());
+            faces.push(Face {
+                v: [vi, *v, w],
+                n: [n[0] / norm, n[1] / norm, n[2] / norm],
+                c: [
+                    (a[0] + b[0] + c[0]) / 3.0,
+                    (a[1] + b[1] + c[1]) / 3.0,
+                    (a[2] + b[2] + c[2]) / 3.0,
+                ],
+                area: norm / 2.0,
+            });
+        }
+    }
+    //-- the normals filtered with those of the triangles sharing an edge
+    let gaussian = |d2: f64, sigma: f64| (-d2 / (2.0 * sigma * sigma)).exp();
+    let filtered: Vec<[f64; 3]> = faces
+        .iter()
+        .map(|f| {
+            let mut n = f.n.map(|x| x * f.area);
+            for j in 0..3 {
+                let (a, b) = (f.v[j], f.v[(j + 1) % 3]);
+                let d = dt.stars[b].link.get_next_vertex(a).unwrap();
+                if d == 0 {
+                    continue;
+                }
+                let g = &faces[index[&triangle_key(&[b, a, d])]];
+                let w = g.area
+                    * gaussian(sq_distance(&f.c, &g.c), sigma_distance)
+                    * gaussian(sq_distance(&f.n, &g.n), sigma_normal);
+                for (nk, gk) in n.iter_mut().zip(g.n) {
+                    *nk += w * gk;
+                }
+            }
+            let norm = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            n.map(|x| x / norm)
+        })
+        .collect();
+    //-- each vertex is moved vertically towards the planes of its incident triangles
+    let newzs: Vec<f64> = free
+        .iter()
+        .map(|vi| {
+            let p = dt.stars[*vi].pt;
+            let l = &dt.stars[*vi].link;
+            let (mut dz, mut sumw) = (0.0, 0.0);
+            for (i, v) in l.iter().enumerate() {
+                let w = l[l.next_index(i)];
+                if *v == 0 || w == 0 {
+                    continue;
+                }
+                let fi = index[&triangle_key(&[*vi, *v, w])];
+                let (f, n) = (&faces[fi], &filtered[fi]);
+                let offset =
+                    n[0] * (f.c[0] - p[0]) + n[1] * (f.c[1] - p[1]) + n[2] * (f.c[2] - p[2]);
+                dz += f.area * n[2] * offset;
+                sumw += f.area;
+            }
+            p[2] + dz / sumw
+        })
+        .collect();
+    for (vi, z) in free.iter().zip(newzs) {
+        dt.stars[*vi].pt[2] = z;
+    }
+}
+
+fn sq_distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
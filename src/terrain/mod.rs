@@ -0,0 +1,367 @@
+//! # terrain
+//!
+//! Terrain derivatives: slope and aspect of the triangles and of the vertices, and the
+//! curvatures at the vertices.
+//! They can be stored as attributes of the vertices/triangles, or computed for the cells
+//! of a raster ([`crate::interpolation::Grid`]).
+
+use crate::attributes::{AttributeType, AttributeValue};
+use crate::interpolation::{Grid, WeightedInterpolant, TIN};
+use crate::StartinError;
+use crate::Triangle;
+use crate::Triangulation;
+
+use crate::geom;
+
+/// The unit of the slope
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlopeUnit {
+    Degrees,
+    /// 100 * the tangent of the angle (45 degrees is 100%)
+    Percent,
+}
+
+/// A terrain derivative
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Derivative {
+    Slope(SlopeUnit),
+    /// in degrees clockwise from the north (90 is east), the direction the terrain faces,
+    /// `f64::NAN` if it is flat
+    Aspect,
+    /// curvature of the contour lines, positive for convex terrain (a spur),
+    /// 0.0 if the terrain is flat
+    PlanCurvature,
+    /// curvature in the direction of the steepest slope, positive for convex terrain
+    /// (the slope gets steeper downhill), 0.0 if the terrain is flat
+    ProfileCurvature,
+    /// mean curvature of the surface, positive for convex terrain (a hill)
+    MeanCurvature,
+}
+
+impl Derivative {
+    /// The name of the attribute used to store it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Derivative::Slope(SlopeUnit::Degrees) => "slope_deg",
+            Derivative::Slope(SlopeUnit::Percent) => "slope_pct",
+            Derivative::Aspect => "aspect",
+            Derivative::PlanCurvature => "plan_curvature",
+            Derivative::ProfileCurvature => "profile_curvature",
+            Derivative::MeanCurvature => "mean_curvature",
+        }
+    }
+}
+
+/// The curvatures at a vertex, see [`Derivative`] for their definitions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Curvatures {
+    pub plan: f64,
+    pub profile: f64,
+    pub mean: f64,
+}
+
+/// Slope of the triangle `tr`
+pub fn slope_triangle(
+    dt: &Triangulation,
+    tr: &Triangle,
+    unit: SlopeUnit,
+) -> Result<f64, StartinError> {
+    Ok(slope(triangle_gradient(dt, tr)?, unit))
+}
+
+/// Aspect of the triangle `tr`
+pub fn aspect_triangle(dt: &Triangulation, tr: &Triangle) -> Result<f64, StartinError> {
+    Ok(aspect(triangle_gradient(dt, tr)?))
+}
+
+/// Slope at the vertex `vi`, from its normal (see [`Triangulation::normal_vertex()`])
+pub fn slope_vertex(dt: &Triangulation, vi: usize, unit: SlopeUnit) -> Result<f64, StartinError> {
+    Ok(slope(vertex_gradient(dt, vi)?, unit))
+}
+
+/// Aspect at the vertex `vi`, from its normal (see [`Triangulation::normal_vertex()`])
+pub fn aspect_vertex(dt: &Triangulation, vi: usize) -> Result<f64, StartinError> {
+    Ok(aspect(vertex_gradient(dt, vi)?))
+}
+
+/// The curvatures at the vertex `vi`, from the quadratic surface fitted (least squares) to its
+/// adjacent vertices (and to the adjacent vertices of those if there are less than 5), and that
+/// passes through `vi`.
+/// Returns [`StartinError::NotEnoughData`] if there are not enough vertices to fit the surface.
+pub fn curvature_vertex(dt: &Triangulation, vi: usize) -> Result<Curvatures, StartinError> {
+    if vi == 0 {
+        return Err(StartinError::VertexInfinite);
+    }
+    let mut ns: Vec<usize> = dt
+        .adjacent_vertices_to_vertex(vi)?
+        .into_iter()
+        .filter(|v| *v != 0)
+        .collect();
+    if ns.len() < 5 {
+        for v in ns.clone() {
+            for w in dt.stars[v].link.iter() {
+                if *w != 0 && *w != vi && !ns.contains(w) {
+                    ns.push(*w);
+                }
+            }
+        }
+    }
+    if ns.len() < 5 {
+        return Err(StartinError::NotEnoughData);
+    }
+    //-- z - z0 = a*dx^2 + b*dy^2 + c*dx*dy + d*dx + e*dy
+    let p0 = &dt.stars[vi].pt;
+    let mut ata = vec![vec![0.0; 5]; 5];
+    let mut atb = vec![0.0; 5];
+    for v in &ns {
+        let p = &dt.stars[*v].pt;
+        let (dx, dy) = (p[0] - p0[0], p[1] - p0[1]);
+        let row = [dx * dx, dy * dy, dx * dy, dx, dy];
+        for i in 0..5 {
+            for j in 0..5 {
+                ata[i][j] += row[i] * row[j];
+            }
+            atb[i] += row[i] * (p[2] - p0[2]);
+        }
+    }
+    let coefs = geom::solve_linear_system(ata, atb).ok_or(StartinError::SingularMatrix)?;
+    let (r, t, s, p, q) = (2.0 * coefs[0], 2.0 * coefs[1], coefs[2], coefs[3], coefs[4]);
+    let g2 = p * p + q * q;
+    let mean =
+        -((1.0 + q * q) * r - 2.0 * p * q * s + (1.0 + p * p) * t) / (2.0 * (1.0 + g2).powf(1.5));
+    if g2 == 0.0 {
+        return Ok(Curvatures {
+            plan: 0.0,
+            profile: 0.0,
+            mean,
+        });
+    }
+    Ok(Curvatures {
+        plan: -(q * q * r - 2.0 * p * q * s + p * p * t) / g2.powf(1.5),
+        profile: -(p * p * r + 2.0 * p * q * s + q * q * t) / (g2 * (1.0 + g2).powf(1.5)),
+        mean,
+    })
+}
+
+/// The derivative `d` at the vertex `vi`
+pub fn vertex_derivative(
+    dt: &Triangulation,
+    vi: usize,
+    d: Derivative,
+) -> Result<f64, StartinError> {
+    vertex_derivative_with(dt, vi, d, &mut |vi| curvature_vertex(dt, vi))
+}
+
+/// The derivative `d` of the triangle `tr`; since a triangle is planar its curvatures are
+/// the averages of those of its 3 vertices.
+pub fn triangle_derivative(
+    dt: &Triangulation,
+    tr: &Triangle,
+    d: Derivative,
+) -> Result<f64, StartinError> {
+    triangle_derivative_with(dt, tr, d, &mut |vi| curvature_vertex(dt, vi))
+}
+
+/// Stores the derivatives as attributes of the vertices (of type "f64", named with
+/// [`Derivative::name()`]), the attributes are added to the schema if needed.
+/// The vertices for which a derivative cannot be computed don't get a value.
+pub fn store_vertex_derivatives(
+    dt: &mut Triangulation,
+    ds: &[Derivative],
+) -> Result<(), StartinError> {
+    for d in ds {
+        if !dt
+            .get_attributes_schema()
+            .iter()
+            .any(|(name, _)| name == d.name())
+        {
+            dt.add_attribute(d.name(), AttributeType::F64, None)?;
+        }
+    }
+    let mut values: Vec<(usize, Derivative, f64)> = Vec::new();
+    {
+        //-- the curvatures of a vertex are computed only once for all the derivatives
+        let mut curvatures = curvatures_cache(dt);
+        for vi in 1..dt.stars.len() {
+            if dt.stars[vi].is_deleted() {
+                continue;
+            }
+            for d in ds {
+                if let Ok(v) = vertex_derivative_with(dt, vi, *d, &mut curvatures) {
+                    values.push((vi, *d, v));
+                }
+            }
+        }
+    }
+    for (vi, d, v) in values {
+        dt.set_vertex_attribute(vi, d.name(), AttributeValue::F64(v))?;
+    }
+    Ok(())
+}
+
+/// Stores the derivatives as attributes of the finite triangles (of type "f64", named with
+/// [`Derivative::name()`]), the attributes are added to the schema of the triangles if needed.
+/// The triangles for which a derivative cannot be computed don't get a value.
+pub fn store_triangle_derivatives(
+    dt: &mut Triangulation,
+    ds: &[Derivative],
+) -> Result<(), StartinError> {
+    let mut schema = dt.get_triangle_attributes_schema();
+    for d in ds {
+        if !schema.iter().any(|(name, _)| name == d.name()) {
+            schema.push((d.name().to_string(), "f64".to_string()));
+        }
+    }
+    dt.set_triangle_attributes_schema(schema)?;
+    let mut values: Vec<(Triangle, Derivative, f64)> = Vec::new();
+    {
+        //-- the curvatures of a vertex are computed only once for all its triangles
+        let mut curvatures = curvatures_cache(dt);
+        for tr in dt.all_finite_triangles() {
+            for d in ds {
+                if let Ok(v) = triangle_derivative_with(dt, &tr, *d, &mut curvatures) {
+                    values.push((tr.clone(), *d, v));
+                }
+            }
+        }
+    }
+    for (tr, d, v) in values {
+        dt.set_triangle_attribute(&tr, d.name(), AttributeValue::F64(v))?;
+    }
+    Ok(())
+}
+
+/// The derivative `d` at the centres of the cells of the grid: the values at the vertices
+/// are linearly interpolated in the triangles (for the slope and the aspect, the gradients
+/// are interpolated).
+/// Can be written to a file with [`Grid::write_asc()`].
+pub fn derivative_grid(
+    dt: &Triangulation,
+    d: Derivative,
+    grid: &Grid,
+) -> Vec<Result<f64, StartinError>> {
+    //-- the values at the vertices (the gradient for slope/aspect) computed only once
+    let mut cache: Vec<Option<[f64; 2]>> = vec![None; dt.stars.len()];
+    let mut value_at = |vi: usize| -> [f64; 2] {
+        *cache[vi].get_or_insert_with(|| {
+            let re = match d {
+                Derivative::Slope(_) | Derivative::Aspect => vertex_gradient(dt, vi),
+                _ => vertex_derivative(dt, vi, d).map(|v| [v, 0.0]),
+            };
+            re.unwrap_or([f64::NAN, f64::NAN])
+        })
+    };
+    let i_tin = TIN {};
+    i_tin
+        .weights(dt, &grid.cell_centres())
+        .into_iter()
+        .map(|r| {
+            let ws = r?;
+            let mut v = [0.0, 0.0];
+            for (vi, w) in ws {
+                let vv = value_at(vi);
+                v[0] += w * vv[0];
+                v[1] += w * vv[1];
+            }
+            Ok(match d {
+                Derivative::Slope(unit) => slope(v, unit),
+                Derivative::Aspect => aspect(v),
+                _ => v[0],
+            })
+        })
+        .collect()
+}
+
+/// The curvatures of the vertices (see [`curvature_vertex()`]), each computed when it is
+/// first needed
+fn curvatures_cache(
+    dt: &Triangulation,
+) -> impl FnMut(usize) -> Result<Curvatures, StartinError> + '_ {
+    let mut cache: Vec<Option<Result<Curvatures, StartinError>>> = vec![None; dt.stars.len()];
+    move |vi| {
+        cache[vi]
+            .get_or_insert_with(|| curvature_vertex(dt, vi))
+            .clone()
+    }
+}
+
+/// The derivative `d` at the vertex `vi`, `curvatures` returns the curvatures of a vertex
+fn vertex_derivative_with(
+    dt: &Triangulation,
+    vi: usize,
+    d: Derivative,
+    curvatures: &mut impl FnMut(usize) -> Result<Curvatures, StartinError>,
+) -> Result<f64, StartinError> {
+    match d {
+        Derivative::Slope(unit) => slope_vertex(dt, vi, unit),
+        Derivative::Aspect => aspect_vertex(dt, vi),
+        Derivative::PlanCurvature => Ok(curvatures(vi)?.plan),
+        Derivative::ProfileCurvature => Ok(curvatures(vi)?.profile),
+        Derivative::MeanCurvature => Ok(curvatures(vi)?.mean),
+    }
+}
+
+/// The derivative `d` of the triangle `tr`, `curvatures` returns the curvatures of a vertex
+fn triangle_derivative_with(
+    dt: &Triangulation,
+    tr: &Triangle,
+    d: Derivative,
+    curvatures: &mut impl FnMut(usize) -> Result<Curvatures, StartinError>,
+) -> Result<f64, StartinError> {
+    match d {
+        Derivative::Slope(unit) => slope_triangle(dt, tr, unit),
+        Derivative::Aspect => aspect_triangle(dt, tr),
+        _ => {
+            if !dt.is_triangle(tr) || tr.is_infinite() {
+                return Err(StartinError::TriangleNotPresent);
+            }
+            let mut sum = 0.0;
+            for vi in tr.v {
+                sum += vertex_derivative_with(dt, vi, d, curvatures)?;
+            }
+            Ok(sum / 3.0)
+        }
+    }
+}
+
+/// The gradient (dz/dx, dz/dy) of a finite triangle
+pub(crate) fn triangle_gradient(
+    dt: &Triangulation,
+    tr: &Triangle,
+) -> Result<[f64; 2], StartinError> {
+    if tr.is_infinite() {
+        return Err(StartinError::TriangleNotPresent);
+    }
+    let n = dt.normal_triangle(tr)?;
+    Ok([-n[0] / n[2], -n[1] / n[2]])
+}
+
+/// The gradient (dz/dx, dz/dy) at a vertex, from its normal
+fn vertex_gradient(dt: &Triangulation, vi: usize) -> Result<[f64; 2], StartinError> {
+    if vi == 0 {
+        return Err(StartinError::VertexInfinite);
+    }
+    let n = dt.normal_vertex(vi)?;
+    Ok([-n[0] / n[2], -n[1] / n[2]])
+}
+
+fn slope(g: [f64; 2], unit: SlopeUnit) -> f64 {
+    let tan = (g[0] * g[0] + g[1] * g[1]).sqrt();
+    match unit {
+        SlopeUnit::Degrees => tan.atan().to_degrees(),
+        SlopeUnit::Percent => 100.0 * tan,
+    }
+}
+
+fn aspect(g: [f64; 2]) -> f64 {
+    if g[0] == 0.0 && g[1] == 0.0 {
+        return f64::NAN;
+    }
+    //-- the terrain faces the direction of the steepest descent (-g)
+    let a = (-g[0]).atan2(-g[1]).to_degrees();
+    if a < 0.0 {
+        a + 360.0
+    } else {
+        a
+    }
+}
@@ -0,0 +1,191 @@
+//! # visibility
+//!
+//! Line-of-sight between two points over the surface of the TIN, the viewshed of an
+//! observer (the vertices and the triangles that are visible from it), and the intersection
+//! of 3D rays with the TIN.
+//! The segment between the points (or the projection of the ray) is followed through the
+//! triangulation (see [`crate::profile`]) and tested against the TIN at all its crossings
+//! with the edges and the vertices, since the TIN is linear between them the test is exact.
+
+use crate::profile::{trace_segment, z_in_triangle};
+use crate::StartinError;
+use crate::Triangle;
+use crate::Triangulation;
+
+/// Returns whether the 3D points `a` and `b` are visible from each other: the segment
+/// between them is nowhere below the surface of the TIN (it can touch it).
+/// Returns [`StartinError::OutsideConvexHull`] if one of the points is outside the convex hull.
+pub fn line_of_sight(dt: &Triangulation, a: &[f64; 3], b: &[f64; 3]) -> Result<bool, StartinError> {
+    los(dt, a, b, None)
+}
+
+fn los(
+    dt: &Triangulation,
+    a: &[f64; 3],
+    b: &[f64; 3],
+    mut start: Option<usize>,
+) -> Result<bool, StartinError> {
+    let cs = trace_segment(dt, &[a[0], a[1]], &[b[0], b[1]], &mut start, false)?;
+    //-- the end points themselves do not block the view
+    Ok(cs
+        .iter()
+        .filter(|c| c.t > 0.0 && c.t < 1.0)
+        .all(|c| c.pt[2] <= a[2] + c.t * (b[2] - a[2])))
+}
+
+/// The viewshed of an observer, see [`viewshed()`]
+#[derive(Debug, Clone)]
+pub struct Viewshed {
+    /// the position of the observer (its z is that of the TIN plus its height)
+    pub observer: [f64; 3],
+    /// the visibility of each vertex, indexed by the vertex id
+    /// (the infinite vertex and the removed vertices are `false`)
+    pub vertices: Vec<bool>,
+    /// the finite triangles, and whether they are visible (all 3 of their vertices are)
+    pub triangles: Vec<(Triangle, bool)>,
+}
+
+impl Viewshed {
+    /// The ids of the visible vertices
+    pub fn visible_vertices(&self) -> Vec<usize> {
+        self.vertices
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// The visible triangles
+    pub fn visible_triangles(&self) -> Vec<Triangle> {
+        self.triangles
+            .iter()
+            .filter(|(_, b)| *b)
+            .map(|(tr, _)| tr.clone())
+            .collect()
+    }
+
+    /// The area (in 2D) of the visible triangles
+    pub fn visible_area(&self, dt: &Triangulation) -> f64 {
+        self.triangles
+            .iter()
+            .filter(|(_, b)| *b)
+            .map(|(tr, _)| dt.area2d_triangle(tr).unwrap_or(0.0))
+            .sum()
+    }
+}
+
+/// Computes the viewshed of an observer at (`x`, `y`) and at the height `height` above the
+/// TIN: each vertex is visible if there is a line-of-sight between the observer and it
+/// (the vertices are targets at the surface), and a triangle is visible if its 3 vertices are.
+/// Returns [`StartinError::OutsideConvexHull`] if the observer is outside the convex hull.
+pub fn viewshed(dt: &Triangulation, x: f64, y: f64, height: f64) -> Result<Viewshed, StartinError> {
+    let tr = dt.locate_from(x, y, None)?;
+    let observer = [x, y, z_in_triangle(dt, &tr.v, &[x, y]) + height];
+    let mut vertices = vec![false; dt.stars.len()];
+    for (vi, b) in vertices.iter_mut().enumerate().skip(1) {
+        if !dt.stars[vi].is_deleted() {
+            *b = los(dt, &observer, &dt.stars[vi].pt, Some(tr.v[0]))?;
+        }
+    }
+    let triangles = dt
+        .all_finite_triangles()
+        .into_iter()
+        .map(|tr| {
+            let b = tr.v.iter().all(|v| vertices[*v]);
+            (tr, b)
+        })
+        .collect();
+    Ok(Viewshed {
+        observer,
+        vertices,
+        triangles,
+    })
+}
+
+/// The first intersection of a ray with the TIN, see [`intersect_ray()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit {
+    pub point: [f64; 3],
+    /// the triangle hit (if the point is on an edge or a vertex, one of its incident triangles)
+    pub triangle: Triangle,
+    /// the distance (in 3D) from the origin of the ray
+    pub distance: f64,
+}
+
+/// Returns the first intersection of the ray (starting at `origin` and going in the direction
+/// `direction`, which does not need to be normalised) with the TIN, or `None` if the ray
+/// leaves the convex hull without hitting it.
+/// Only the triangles along the projection of the ray in 2D are tested.
+/// If the origin is on or below the surface, it is itself the hit (with a distance of 0.0).
+/// Returns [`StartinError::OutsideConvexHull`] if the origin is outside the convex hull (in 2D).
+pub fn intersect_ray(
+    dt: &Triangulation,
+    origin: &[f64; 3],
+    direction: &[f64; 3],
+) -> Result<Option<RayHit>, StartinError> {
+    ray(dt, origin, direction, &mut None)
+}
+
+/// Returns the first intersection with the TIN of each ray (origin, direction), see
+/// [`intersect_ray()`]. The walk to locate an origin starts from the previous one, so rays
+/// that are close to each other should be consecutive.
+pub fn intersect_rays(
+    dt: &Triangulation,
+    rays: &[([f64; 3], [f64; 3])],
+) -> Vec<Result<Option<RayHit>, StartinError>> {
+    let mut start = None;
+    rays.iter()
+        .map(|(o, d)| ray(dt, o, d, &mut start))
+        .collect()
+}
+
+fn ray(
+    dt: &Triangulation,
+    o: &[f64; 3],
+    d: &[f64; 3],
+    start: &mut Option<usize>,
+) -> Result<Option<RayHit>, StartinError> {
+    let len2d = (d[0] * d[0] + d[1] * d[1]).sqrt();
+    let len3d = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+    //-- the projection of the ray is traced up to a point surely outside the convex hull
+    let bbox = dt.get_bbox();
+    let reach = 2.0 * ((bbox[2] - bbox[0]).powi(2) + (bbox[3] - bbox[1]).powi(2)).sqrt();
+    let far = if len2d > 0.0 {
+        [o[0] + reach * d[0] / len2d, o[1] + reach * d[1] / len2d]
+    } else {
+        [o[0], o[1]]
+    };
+    let cs = trace_segment(dt, &[o[0], o[1]], &far, start, true)?;
+    //-- the parameter s of the ray (origin + s * direction) above the crossing at t,
+    //-- and the height of the ray above the TIN there
+    let s_at = |t: f64| if len2d > 0.0 { t * reach / len2d } else { 0.0 };
+    let h = |t: f64, z: f64| o[2] + s_at(t) * d[2] - z;
+    let h0 = h(0.0, cs[0].pt[2]);
+    let hit = |s: f64, triangle: [usize; 3]| RayHit {
+        point: [o[0] + s * d[0], o[1] + s * d[1], o[2] + s * d[2]],
+        triangle: Triangle { v: triangle },
+        distance: s * len3d,
+    };
+    if h0 <= 0.0 {
+        return Ok(Some(hit(0.0, cs[0].triangle)));
+    }
+    if len2d == 0.0 {
+        //-- a vertical ray
+        if d[2] < 0.0 {
+            return Ok(Some(hit(-h0 / d[2], cs[0].triangle)));
+        }
+        return Ok(None);
+    }
+    let mut hprev = h0;
+    for w in cs.windows(2) {
+        let hcur = h(w[1].t, w[1].pt[2]);
+        if hcur <= 0.0 {
+            //-- the TIN and the ray are linear between 2 crossings
+            let t = w[0].t + (w[1].t - w[0].t) * hprev / (hprev - hcur);
+            return Ok(Some(hit(s_at(t), w[1].triangle)));
+        }
+        hprev = hcur;
+    }
+    Ok(None)
+}
@@ -0,0 +1,303 @@
+use serde_json::json;
+use startin::attributes::{
+    AttributeType, AttributeValue, ColumnValues, InheritanceRules, TriangleInheritance,
+};
+use startin::Triangulation;
+
+fn five_points_with_schema() -> Triangulation {
+    let pts: Vec<[f64; 3]> = vec![
+        [0.0, 0.0, 1.0],
+        [10.0, 0.0, 2.0],
+        [10.0, 10.0, 3.0],
+        [0.0, 10.0, 4.0],
+        [5.0, 5.0, 10.0],
+    ];
+    let mut dt = startin::Triangulation::new();
+    let _ = dt.set_attributes_schema(vec![
+        ("intensity".to_string(), "f64".to_string()),
+        ("classification".to_string(), "u64".to_string()),
+        ("visited".to_string(), "bool".to_string()),
+    ]);
+    dt.insert(&pts, startin::InsertionStrategy::AsIs);
+    dt
+}
+
+#[test]
+fn wrong_schema() {
+    let mut dt = startin::Triangulation::new();
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        dt.set_attributes_schema(vec![("a".to_string(), "f32".to_string())])
+    );
+    assert_eq!(
+        Err(startin::StartinError::TinHasNoAttributes),
+        dt.attribute_column("a").map(|_| ())
+    );
+}
+
+#[test]
+fn json_compatibility() {
+    let mut dt = five_points_with_schema();
+    let _ = dt.add_vertex_attributes(5, json!({"intensity": 33.3, "visited": true, "hello": 1}));
+    //-- wrong type is ignored
+    let _ = dt.add_vertex_attributes(5, json!({"classification": -2}));
+    assert_eq!(
+        json!({"intensity": 33.3, "visited": true}),
+        dt.get_vertex_attributes(5).unwrap()
+    );
+    assert_eq!(json!({}), dt.get_vertex_attributes(1).unwrap());
+    assert_eq!(6, dt.all_attributes().unwrap().len());
+}
+
+#[test]
+fn typed_access() {
+    let mut dt = five_points_with_schema();
+    assert_eq!(Ok(None), dt.get_vertex_attribute(2, "intensity"));
+    let _ = dt.set_vertex_attribute(2, "intensity", AttributeValue::F64(1.5));
+    let _ = dt.set_vertex_attribute(2, "classification", AttributeValue::U64(6));
+    assert_eq!(
+        Ok(Some(AttributeValue::F64(1.5))),
+        dt.get_vertex_attribute(2, "intensity")
+    );
+    assert_eq!(
+        Ok(Some(6.0)),
+        dt.get_vertex_attribute_f64(2, "classification")
+    );
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        dt.set_vertex_attribute(2, "intensity", AttributeValue::Bool(true))
+    );
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        dt.get_vertex_attribute(2, "nope")
+    );
+}
+
+#[test]
+fn columns() {
+    let mut dt = five_points_with_schema();
+    let re = dt.set_attribute_column(
+        "intensity",
+        ColumnValues::F64(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]),
+    );
+    assert!(re.is_ok());
+    let c = dt.attribute_column("intensity").unwrap();
+    assert_eq!(AttributeType::F64, c.dtype());
+    assert_eq!(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0], c.as_f64().unwrap());
+    assert!(!c.is_set(0));
+    assert!(c.is_set(3));
+    //-- wrong length or wrong type
+    assert!(dt
+        .set_attribute_column("intensity", ColumnValues::F64(vec![0.0, 1.0]))
+        .is_err());
+    assert!(dt
+        .set_attribute_column("visited", ColumnValues::U64(vec![0; 6]))
+        .is_err());
+}
+
+#[test]
+fn remove_and_reuse() {
+    let mut dt = five_points_with_schema();
+    let _ = dt.set_vertex_attribute(5, "intensity", AttributeValue::F64(9.0));
+    let _ = dt.set_vertex_attribute(4, "intensity", AttributeValue::F64(4.0));
+    let _ = dt.remove(5);
+    let vi = dt.insert_one_pt(6.0, 4.0, 1.0).unwrap();
+    assert_eq!(5, vi);
+    assert_eq!(Ok(None), dt.get_vertex_attribute(vi, "intensity"));
+    assert_eq!(6, dt.all_attributes().unwrap().len());
+    let _ = dt.remove(2);
+    dt.collect_garbage();
+    assert_eq!(5, dt.all_attributes().unwrap().len());
+    assert_eq!(
+        Ok(Some(AttributeValue::F64(4.0))),
+        dt.get_vertex_attribute(3, "intensity")
+    );
+}
+
+#[test]
+fn schema_evolution() {
+    let mut dt = five_points_with_schema();
+    let _ = dt.set_vertex_attribute(3, "intensity", AttributeValue::F64(3.3));
+    let _ = dt.set_vertex_attribute(3, "visited", AttributeValue::Bool(true));
+    //-- add a column with a default value
+    assert!(dt
+        .add_attribute(
+            "thickness",
+            AttributeType::F64,
+            Some(AttributeValue::F64(1.0))
+        )
+        .is_ok());
+    assert_eq!(Ok(Some(1.0)), dt.get_vertex_attribute_f64(4, "thickness"));
+    assert_eq!(Ok(Some(3.3)), dt.get_vertex_attribute_f64(3, "intensity"));
+    //-- type conflicts
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        dt.add_attribute("thickness", AttributeType::I64, None)
+    );
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        dt.add_attribute("depth", AttributeType::I64, Some(AttributeValue::F64(1.0)))
+    );
+    //-- rename and remove
+    assert!(dt.rename_attribute("intensity", "i").is_ok());
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        dt.rename_attribute("i", "visited")
+    );
+    assert!(dt.remove_attribute("classification").is_ok());
+    assert_eq!(
+        json!({"i": 3.3, "visited": true, "thickness": 1.0}),
+        dt.get_vertex_attributes(3).unwrap()
+    );
+}
+
+#[test]
+fn schema_replaced() {
+    let mut dt = five_points_with_schema();
+    let _ = dt.set_vertex_attribute(3, "intensity", AttributeValue::F64(3.3));
+    let _ = dt.set_vertex_attribute(3, "visited", AttributeValue::Bool(true));
+    assert!(dt
+        .set_attributes_schema(vec![
+            ("intensity".to_string(), "f64".to_string()),
+            ("label".to_string(), "String".to_string()),
+        ])
+        .is_ok());
+    assert_eq!(
+        vec![
+            ("intensity".to_string(), "f64".to_string()),
+            ("label".to_string(), "String".to_string())
+        ],
+        dt.get_attributes_schema()
+    );
+    assert_eq!(
+        json!({"intensity": 3.3}),
+        dt.get_vertex_attributes(3).unwrap()
+    );
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        dt.set_attributes_schema(vec![("intensity".to_string(), "i64".to_string())])
+    );
+    assert_eq!(2, dt.get_attributes_schema().len());
+}
+
+fn four_points_with_landcover() -> Triangulation {
+    let pts: Vec<[f64; 3]> = vec![
+        [0.0, 0.0, 1.0],
+        [10.0, 0.0, 2.0],
+        [10.0, 10.0, 3.0],
+        [0.0, 10.0, 4.0],
+    ];
+    let mut dt = startin::Triangulation::new();
+    dt.insert(&pts, startin::InsertionStrategy::AsIs);
+    let _ = dt.set_triangle_attributes_schema(vec![("landcover".to_string(), "i64".to_string())]);
+    let _ = dt.set_edge_attributes_schema(vec![("breakline".to_string(), "u64".to_string())]);
+    for tr in dt.all_finite_triangles() {
+        let _ = dt.set_triangle_attribute(&tr, "landcover", AttributeValue::I64(7));
+    }
+    dt
+}
+
+#[test]
+fn triangle_attributes_flip13() {
+    let mut dt = four_points_with_landcover();
+    let vi = dt.insert_one_pt(2.0, 5.0, 1.0).unwrap();
+    for tr in dt.incident_triangles_to_vertex(vi).unwrap() {
+        if dt.is_finite(&tr) {
+            assert_eq!(
+                Ok(Some(AttributeValue::I64(7))),
+                dt.get_triangle_attribute(&tr, "landcover")
+            );
+        }
+    }
+    //-- dropped
+    dt.set_attributes_inheritance(InheritanceRules {
+        flip13: TriangleInheritance::Drop,
+        flip22: TriangleInheritance::Drop,
+        split_edges: false,
+    });
+    let vi = dt.insert_one_pt(8.0, 5.0, 1.0).unwrap();
+    let tr = dt.incident_triangles_to_vertex(vi).unwrap()[0].clone();
+    assert_eq!(Ok(json!({})), dt.get_triangle_attributes(&tr));
+}
+
+#[test]
+fn triangle_attributes_flip22() {
+    let mut dt = four_points_with_landcover();
+    let trs = dt.all_finite_triangles();
+    let _ = dt.set_triangle_attribute(&trs[0], "landcover", AttributeValue::I64(1));
+    dt.set_attributes_inheritance(InheritanceRules {
+        flip13: TriangleInheritance::LargestOverlap,
+        flip22: TriangleInheritance::IfEqual,
+        split_edges: true,
+    });
+    //-- on the diagonal, the 4 triangles are created with flips
+    let vi = dt.insert_one_pt(5.0, 5.0, 1.0).unwrap();
+    let mut n = 0;
+    for tr in dt.incident_triangles_to_vertex(vi).unwrap() {
+        if dt.get_triangle_attribute(&tr, "landcover") == Ok(None) {
+            n += 1;
+        }
+    }
+    assert_eq!(2, n);
+    //-- removal drops the attributes of the incident triangles
+    let _ = dt.remove(vi);
+    for tr in dt.all_finite_triangles() {
+        assert_eq!(Ok(None), dt.get_triangle_attribute(&tr, "landcover"));
+    }
+}
+
+#[test]
+fn edge_attributes_split() {
+    let mut dt = four_points_with_landcover();
+    assert_eq!(
+        Err(startin::StartinError::EdgeNotPresent),
+        dt.set_edge_attribute(1, 0, "breakline", AttributeValue::U64(1))
+    );
+    let _ = dt.set_edge_attribute(1, 2, "breakline", AttributeValue::U64(3));
+    let vi = dt.insert_one_pt(5.0, 0.0, 1.0).unwrap();
+    assert!(!dt.is_finite_edge(1, 2));
+    assert_eq!(
+        Ok(Some(AttributeValue::U64(3))),
+        dt.get_edge_attribute(vi, 1, "breakline")
+    );
+    assert_eq!(
+        Ok(Some(AttributeValue::U64(3))),
+        dt.get_edge_attribute(2, vi, "breakline")
+    );
+    //-- ids are updated when garbage is collected
+    let _ = dt.remove(4);
+    dt.collect_garbage();
+    assert_eq!(
+        Ok(Some(AttributeValue::U64(3))),
+        dt.get_edge_attribute(1, vi - 1, "breakline")
+    );
+}
+
+#[test]
+fn ply_face_properties() {
+    let dt = four_points_with_landcover();
+    let path = std::env::temp_dir().join("startin_ply_face_properties.ply");
+    let _ = dt.write_ply(path.to_str().unwrap().to_string());
+    let s = std::fs::read_to_string(&path).unwrap();
+    assert!(s.contains("property list uchar int vertex_indices\nproperty int landcover\n"));
+    assert!(s.contains("element edge 5\n"));
+    assert_eq!(
+        2,
+        s.lines()
+            .filter(|l| l.starts_with("3 ") && l.ends_with(" 7"))
+            .count()
+    );
+}
+
+#[test]
+fn interpolation_keeps_triangle_attributes() {
+    let dt = four_points_with_landcover();
+    let i_nni = startin::interpolation::NNI { precompute: false };
+    let _ = startin::interpolation::interpolate(&i_nni, &dt, &vec![[3.0, 4.0]]);
+    for tr in dt.all_finite_triangles() {
+        assert_eq!(
+            Ok(Some(AttributeValue::I64(7))),
+            dt.get_triangle_attribute(&tr, "landcover")
+        );
+    }
+}
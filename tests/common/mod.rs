@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+
+use rand::prelude::*;
+use startin::Triangulation;
+
+/// The grid [0, 10] x [0, 10] (with a spacing of 1.0) with the z-values of `f`
+pub fn grid(f: impl Fn(f64, f64) -> f64) -> Triangulation {
+    let mut dt = Triangulation::new();
+    for i in 0..11 {
+        for j in 0..11 {
+            let _ = dt.insert_one_pt(i as f64, j as f64, f(i as f64, j as f64));
+        }
+    }
+    dt
+}
+
+/// The grid of `n` x `n` points with a spacing of 1.0 (from the origin), each moved randomly by
+/// up to 0.2 in x and y to avoid cocircular points, with the z-values of `f`
+pub fn jittered_grid(n: usize, mut f: impl FnMut(f64, f64) -> f64) -> Triangulation {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for i in 0..n {
+        for j in 0..n {
+            let x = i as f64 + rng.gen::<f64>() * 0.2;
+            let y = j as f64 + rng.gen::<f64>() * 0.2;
+            let _ = dt.insert_one_pt(x, y, f(x, y));
+        }
+    }
+    dt
+}
+
+/// `n` random points in the bbox [minx, miny, maxx, maxy] with the z-values of `f`, and the
+/// corners of the bbox (inserted last) so that the convex hull is the bbox
+pub fn random_surface(bbox: [f64; 4], n: usize, f: impl Fn(f64, f64) -> f64) -> Triangulation {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..n {
+        let x: f64 = bbox[0] + rng.gen::<f64>() * (bbox[2] - bbox[0]);
+        let y: f64 = bbox[1] + rng.gen::<f64>() * (bbox[3] - bbox[1]);
+        let _ = dt.insert_one_pt(x, y, f(x, y));
+    }
+    for p in [[0, 1], [2, 1], [2, 3], [0, 3]] {
+        let (x, y) = (bbox[p[0]], bbox[p[1]]);
+        let _ = dt.insert_one_pt(x, y, f(x, y));
+    }
+    dt
+}
@@ -0,0 +1,167 @@
+mod common;
+use common::grid;
+
+use rand::prelude::*;
+use startin::contours::{contour_lines, isobands};
+use startin::Triangulation;
+
+fn signed_area(pts: &[[f64; 3]]) -> f64 {
+    let mut a = 0.0;
+    for i in 0..pts.len() {
+        let j = (i + 1) % pts.len();
+        a += pts[i][0] * pts[j][1] - pts[j][0] * pts[i][1];
+    }
+    a / 2.0
+}
+
+#[test]
+fn empty() {
+    let dt = Triangulation::new();
+    assert_eq!(
+        Err(startin::StartinError::EmptyTriangulation),
+        contour_lines(&dt, &[1.0])
+    );
+}
+
+#[test]
+fn hill() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..500 {
+        let x: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let y: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let _ = dt.insert_one_pt(x, y, 10.0 - (x * x + y * y).sqrt());
+    }
+    let cs = contour_lines(&dt, &[5.0, 8.0, 20.0]).unwrap();
+    assert_eq!(2, cs.len());
+    for c in &cs {
+        assert!(c.closed);
+        //-- higher ground on the left
+        assert!(signed_area(&c.points) > 0.0);
+        //-- the area of the circle (the chords of the TIN are inside)
+        let r = 10.0 - c.level;
+        let area = std::f64::consts::PI * r * r;
+        assert!(signed_area(&c.points) < area && signed_area(&c.points) > 0.8 * area);
+        for p in &c.points {
+            assert_eq!(c.level, p[2]);
+        }
+    }
+}
+
+#[test]
+fn open_lines() {
+    let dt = grid(|x, y| x + 0.01 * y * y);
+    let cs = contour_lines(&dt, &[4.5, 7.3]).unwrap();
+    assert_eq!(2, cs.len());
+    for c in &cs {
+        assert!(!c.closed);
+        //-- the terrain goes up to the east, so the lines go to the south
+        assert_eq!(10.0, c.points.first().unwrap()[1]);
+        assert_eq!(0.0, c.points.last().unwrap()[1]);
+    }
+}
+
+#[test]
+fn vertices_at_level() {
+    //-- the line passes through the 11 vertices with x=5, no duplicates
+    let dt = grid(|x, _y| x);
+    let cs = contour_lines(&dt, &[5.0]).unwrap();
+    assert_eq!(1, cs.len());
+    assert_eq!(11, cs[0].points.len());
+    for (i, p) in cs[0].points.iter().enumerate() {
+        assert_eq!([5.0, 10.0 - i as f64, 5.0], *p);
+    }
+    //-- a summit exactly at the level has no contour line
+    let dt = grid(|x, y| -((x - 5.0).abs() + (y - 5.0).abs()));
+    assert!(contour_lines(&dt, &[0.0]).unwrap().is_empty());
+    assert_eq!(1, contour_lines(&dt, &[-1.0]).unwrap().len());
+    //-- a flat area at the level
+    let dt = grid(|x, _y| if x < 5.0 { 0.0 } else { 1.0 });
+    let cs = contour_lines(&dt, &[0.0, 1.0]).unwrap();
+    assert_eq!(1, cs.len());
+    assert!(cs[0].points.iter().all(|p| p[0] > 4.0 && p[0] <= 5.0));
+}
+
+fn polygon_area(polygon: &[Vec<[f64; 3]>]) -> f64 {
+    polygon.iter().map(|r| signed_area(&r[..r.len() - 1])).sum()
+}
+
+fn hull_area(dt: &Triangulation) -> f64 {
+    let ch: Vec<[f64; 3]> = dt
+        .convex_hull()
+        .iter()
+        .map(|vi| {
+            let p = dt.get_point(*vi).unwrap();
+            [p[0], p[1], p[2]]
+        })
+        .collect();
+    signed_area(&ch).abs()
+}
+
+#[test]
+fn isobands_hill() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..500 {
+        let x: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let y: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let _ = dt.insert_one_pt(x, y, 10.0 - (x * x + y * y).sqrt());
+    }
+    let bands = isobands(&dt, &[11.0, 5.0, -10.0, 8.0]).unwrap();
+    assert_eq!(3, bands.len());
+    assert_eq!((-10.0, 5.0), (bands[0].lower, bands[0].upper));
+    let cs = contour_lines(&dt, &[5.0, 8.0]).unwrap();
+    let a5 = signed_area(&cs[0].points);
+    let a8 = signed_area(&cs[1].points);
+    //-- the convex hull with a hole
+    assert_eq!(1, bands[0].polygons.len());
+    assert_eq!(2, bands[0].polygons[0].len());
+    assert!((polygon_area(&bands[0].polygons[0]) - (hull_area(&dt) - a5)).abs() < 1e-9);
+    //-- an annulus: the exterior ring is CCW and the hole CW
+    assert_eq!(1, bands[1].polygons.len());
+    let p = &bands[1].polygons[0];
+    assert_eq!(2, p.len());
+    assert!(signed_area(&p[0][..p[0].len() - 1]) > 0.0);
+    assert!(signed_area(&p[1][..p[1].len() - 1]) < 0.0);
+    assert!((polygon_area(p) - (a5 - a8)).abs() < 1e-9);
+    for r in p {
+        assert_eq!(r.first(), r.last());
+    }
+    //-- the top
+    assert_eq!(1, bands[2].polygons.len());
+    assert_eq!(1, bands[2].polygons[0].len());
+    assert!((polygon_area(&bands[2].polygons[0]) - a8).abs() < 1e-9);
+}
+
+#[test]
+fn isobands_cover_the_hull() {
+    //-- many vertices exactly at the breakpoints
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..300 {
+        let x: f64 = rng.gen::<f64>() * 20.0;
+        let y: f64 = rng.gen::<f64>() * 20.0;
+        let _ = dt.insert_one_pt(x, y, rng.gen_range(0, 6) as f64);
+    }
+    let bands = isobands(&dt, &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let total: f64 = bands
+        .iter()
+        .flat_map(|b| b.polygons.iter())
+        .map(|p| polygon_area(p))
+        .sum();
+    assert!((total - hull_area(&dt)).abs() < 1e-9 * hull_area(&dt));
+    for b in &bands {
+        for p in &b.polygons {
+            assert!(signed_area(&p[0][..p[0].len() - 1]) > 0.0);
+            for h in &p[1..] {
+                assert!(signed_area(&h[..h.len() - 1]) < 0.0);
+            }
+        }
+    }
+    //-- a band with vertices exactly at its bounds
+    let dt = grid(|x, _y| x);
+    let bands = isobands(&dt, &[2.0, 5.0]).unwrap();
+    assert_eq!(1, bands[0].polygons.len());
+    assert_eq!(1, bands[0].polygons[0].len());
+    assert!((polygon_area(&bands[0].polygons[0]) - 30.0).abs() < 1e-9);
+}
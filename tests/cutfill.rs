@@ -0,0 +1,104 @@
+mod common;
+use common::{grid, random_surface};
+
+use startin::cutfill::*;
+use startin::Triangulation;
+
+fn volume(dt: &Triangulation) -> f64 {
+    dt.all_finite_triangles()
+        .iter()
+        .map(|tr| dt.volume_triangle(tr, 0.0).unwrap())
+        .sum()
+}
+
+#[test]
+fn planes() {
+    let before = random_surface([0.0, 0.0, 10.0, 10.0], 200, |_x, _y| 0.0);
+    let after = random_surface([0.0, 0.0, 10.0, 10.0], 200, |x, _y| x - 5.0);
+    let cf = cut_fill(&before, &after).unwrap();
+    assert!((cf.area - 100.0).abs() < 1e-9);
+    assert!((cf.fill_volume - 125.0).abs() < 1e-9);
+    assert!((cf.cut_volume - 125.0).abs() < 1e-9);
+    assert!((cf.fill_area - 50.0).abs() < 1e-9);
+    assert!((cf.cut_area - 50.0).abs() < 1e-9);
+    assert!(cf.net_volume().abs() < 1e-9);
+    //-- the difference is linear, so is the difference TIN
+    let diff = difference_tin(&before, &after).unwrap();
+    assert!(diff.vertices.len() >= 400);
+    for p in &diff.vertices {
+        assert!((p[2] - (p[0] - 5.0)).abs() < 1e-9);
+    }
+    assert!((diff.area() - 100.0).abs() < 1e-9);
+    assert!(diff.volume().abs() < 1e-9);
+}
+
+#[test]
+fn surfaces() {
+    //-- the net volume is the difference of the volumes of the 2 TINs
+    let before = random_surface([0.0, 0.0, 10.0, 10.0], 200, |x, y| {
+        (x / 2.0).sin() + (y / 3.0).cos()
+    });
+    let after = random_surface([0.0, 0.0, 10.0, 10.0], 200, |x, y| 0.1 * x * y - 1.0);
+    let cf = cut_fill(&before, &after).unwrap();
+    assert!((cf.net_volume() - (volume(&after) - volume(&before))).abs() < 1e-9);
+    assert!(cf.cut_volume > 0.0 && cf.fill_volume > 0.0);
+    assert!((cf.cut_area + cf.fill_area - 100.0).abs() < 1e-9);
+    //-- the difference TIN is exact, it is not a new TIN of the differences at the vertices
+    let diff = difference_tin(&before, &after).unwrap();
+    assert!((diff.area() - 100.0).abs() < 1e-9);
+    assert!((diff.volume() - cf.net_volume()).abs() < 1e-9 * cf.net_volume().abs().max(1.0));
+    let mut edges: std::collections::HashMap<(usize, usize), usize> = Default::default();
+    for t in &diff.triangles {
+        for i in 0..3 {
+            let (a, b) = (t[i], t[(i + 1) % 3]);
+            *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    //-- the adjacent pieces share their vertices, only the 4 edges of the square are boundaries
+    assert!(edges.values().all(|n| *n <= 2));
+    assert_eq!(4, edges.values().filter(|n| **n == 1).count());
+    //-- swapped
+    let fc = cut_fill(&after, &before).unwrap();
+    assert!((cf.cut_volume - fc.fill_volume).abs() < 1e-9);
+    assert!((cf.fill_area - fc.cut_area).abs() < 1e-9);
+}
+
+#[test]
+fn unchanged() {
+    //-- identical surfaces: neither cut nor fill
+    let before = random_surface([0.0, 0.0, 10.0, 10.0], 200, |x, y| {
+        (x / 2.0).sin() + (y / 3.0).cos()
+    });
+    let cf = cut_fill(&before, &before).unwrap();
+    assert!((cf.unchanged_area - 100.0).abs() < 1e-9);
+    assert_eq!((0.0, 0.0), (cf.cut_area, cf.fill_area));
+    assert_eq!((0.0, 0.0), (cf.cut_volume, cf.fill_volume));
+    //-- only the east half is raised, the west half is the same plane in both
+    let plane = |x: f64, y: f64| 0.3 * x + 0.2 * y + 100.0;
+    let before = random_surface([0.0, 0.0, 10.0, 10.0], 200, plane);
+    let after = grid(|x, y| plane(x, y) + (x - 5.0).max(0.0));
+    let cf = cut_fill(&before, &after).unwrap();
+    assert!(cf.cut_area + cf.fill_area <= cf.area);
+    assert!((cf.cut_area + cf.fill_area + cf.unchanged_area - cf.area).abs() < 1e-9);
+    assert!((cf.unchanged_area - 50.0).abs() < 1e-6);
+    assert!((cf.fill_area - 50.0).abs() < 1e-6);
+    assert!(cf.cut_area < 1e-6);
+}
+
+#[test]
+fn partial_overlap() {
+    let before = random_surface([0.0, 0.0, 10.0, 10.0], 200, |x, _y| x);
+    let after = random_surface([5.0, 0.0, 15.0, 10.0], 200, |x, _y| x + 1.0);
+    let cf = cut_fill(&before, &after).unwrap();
+    assert!((cf.area - 50.0).abs() < 1e-9);
+    assert!((cf.fill_volume - 50.0).abs() < 1e-9);
+    assert_eq!(0.0, cf.cut_volume);
+    assert_eq!(0.0, cf.cut_area);
+    //-- no overlap
+    let after = random_surface([20.0, 0.0, 30.0, 10.0], 200, |x, _y| x);
+    assert_eq!(0.0, cut_fill(&before, &after).unwrap().area);
+    assert_eq!(
+        Err(startin::StartinError::EmptyTriangulation),
+        cut_fill(&before, &Triangulation::new())
+    );
+}
@@ -0,0 +1,269 @@
+mod common;
+use common::grid;
+
+use rand::prelude::*;
+use startin::hydrology::*;
+use startin::Triangulation;
+
+#[test]
+fn steepest_descent() {
+    let dt = grid(|x, y| 0.5 * x + 0.01 * y);
+    let tr = dt.locate_from(4.3, 4.6, None).unwrap();
+    let d = steepest_descent_triangle(&dt, &tr).unwrap().unwrap();
+    let n = (0.25_f64 + 0.0001).sqrt();
+    assert!((d[0] + 0.5 / n).abs() < 1e-12 && (d[1] + 0.01 / n).abs() < 1e-12);
+    //-- the receiver is the west neighbour
+    let vi = dt.closest_point_from(4.0, 4.0, None).unwrap();
+    let r = steepest_descent_vertex(&dt, vi).unwrap().unwrap();
+    assert_eq!([3.0, 4.0], dt.get_point(r).unwrap()[..2]);
+    //-- a pit, and a flat area
+    let vi = dt.closest_point_from(0.0, 0.0, None).unwrap();
+    assert_eq!(None, steepest_descent_vertex(&dt, vi).unwrap());
+    let dt = grid(|_x, _y| 1.0);
+    let tr = dt.locate_from(4.3, 4.6, None).unwrap();
+    assert_eq!(None, steepest_descent_triangle(&dt, &tr).unwrap());
+    assert!(flow_receivers(&dt).unwrap().iter().all(|r| r.is_none()));
+    assert_eq!(
+        Err(startin::StartinError::EmptyTriangulation),
+        flow_receivers(&Triangulation::new())
+    );
+}
+
+#[test]
+fn accumulation_conserves_area() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for p in [[0.0, 0.0], [20.0, 0.0], [20.0, 20.0], [0.0, 20.0]] {
+        let _ = dt.insert_one_pt(p[0], p[1], 0.0);
+    }
+    for _i in 0..300 {
+        let x: f64 = rng.gen::<f64>() * 20.0;
+        let y: f64 = rng.gen::<f64>() * 20.0;
+        let _ = dt.insert_one_pt(x, y, (x / 3.0).sin() + (y / 4.0).cos() + rng.gen::<f64>());
+    }
+    let receivers = flow_receivers(&dt).unwrap();
+    for area in [CellArea::Triangles, CellArea::Voronoi] {
+        let acc = flow_accumulation(&dt, area).unwrap();
+        //-- everything ends in the pits and the outlets
+        let total: f64 = (1..acc.len())
+            .filter(|vi| receivers[*vi].is_none())
+            .map(|vi| acc[vi])
+            .sum();
+        if area == CellArea::Triangles {
+            assert!((total - 400.0).abs() < 1e-9);
+        }
+        for (vi, r) in receivers.iter().enumerate() {
+            if let Some(r) = r {
+                assert!(acc[*r] > acc[vi]);
+                assert!(dt.get_point(*r).unwrap()[2] < dt.get_point(vi).unwrap()[2]);
+            }
+        }
+    }
+}
+
+#[test]
+fn drainage() {
+    //-- a valley along x=5 going down to the south
+    let dt = grid(|x, y| (x - 5.0).abs() + 0.1 * y);
+    let acc = flow_accumulation(&dt, CellArea::Triangles).unwrap();
+    let outlet = dt.closest_point_from(5.0, 0.0, None).unwrap();
+    assert!((acc[outlet] - 100.0).abs() < 1e-9);
+    let lines = drainage_lines(&dt, &acc, 5.0).unwrap();
+    assert_eq!(1, lines.len());
+    let l = &lines[0];
+    assert_eq!(outlet, *l.vertices.last().unwrap());
+    assert_eq!(l.vertices.len(), l.points.len());
+    assert!(l.points.iter().all(|p| p[0] == 5.0));
+    assert!((l.accumulation - 100.0).abs() < 1e-9);
+    //-- a low threshold: the tributaries join the valley at confluences
+    let lines = drainage_lines(&dt, &acc, 1.0).unwrap();
+    assert!(lines.len() > 1);
+    for l in &lines {
+        let last = *l.vertices.last().unwrap();
+        assert!(dt.get_point(last).unwrap()[0] == 5.0);
+    }
+    //-- the accumulation must have one value per vertex
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        drainage_lines(&dt, &acc[..acc.len() - 1], 1.0)
+    );
+}
+
+fn ring_area(r: &[[f64; 3]]) -> f64 {
+    r.windows(2)
+        .map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1])
+        .sum::<f64>()
+        / 2.0
+}
+
+fn polygons_area(c: &Catchment) -> f64 {
+    c.polygons
+        .iter()
+        .flat_map(|p| p.iter())
+        .map(|r| ring_area(r))
+        .sum()
+}
+
+#[test]
+fn two_basins() {
+    let dt = grid(|x, y| {
+        ((x - 2.0).powi(2) + (y - 5.0).powi(2)).min((x - 8.0).powi(2) + (y - 5.0).powi(2))
+            + 0.01 * x
+    });
+    let ps = pits(&dt).unwrap();
+    assert_eq!(2, ps.len());
+    let mut xy: Vec<Vec<f64>> = ps.iter().map(|p| dt.get_point(*p).unwrap()).collect();
+    xy.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    assert_eq!([2.0, 5.0], xy[0][..2]);
+    assert_eq!([8.0, 5.0], xy[1][..2]);
+    let cs = pit_catchments(&dt).unwrap();
+    assert_eq!(121, cs.iter().map(|c| c.vertices.len()).sum::<usize>());
+    assert!((cs[0].area + cs[1].area - 100.0).abs() < 1e-9);
+    for (c, p) in cs.iter().zip(&ps) {
+        assert_eq!(*p, c.outlet);
+        assert!(c.vertices.contains(p));
+        assert_eq!(1, c.polygons.len());
+        assert_eq!(1, c.polygons[0].len());
+        let r = &c.polygons[0][0];
+        assert_eq!(r.first(), r.last());
+        assert!((ring_area(r) - c.area).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn catchment_of_outlet() {
+    let mut dt = grid(|x, y| (x - 5.0).abs() + 0.1 * y);
+    assert!(pits(&dt).unwrap().is_empty());
+    let c = catchment_at_point(&dt, 5.1, 0.1).unwrap();
+    assert_eq!(121, c.vertices.len());
+    assert!((c.area - 100.0).abs() < 1e-9);
+    assert!((polygons_area(&c) - 100.0).abs() < 1e-9);
+    //-- upstream of (5, 5)
+    let c = catchment_at_point(&dt, 5.0, 5.0).unwrap();
+    assert!(c
+        .vertices
+        .iter()
+        .all(|v| dt.get_point(*v).unwrap()[1] >= 5.0));
+    assert!((polygons_area(&c) - c.area).abs() < 1e-9);
+    assert_eq!(
+        Err(startin::StartinError::VertexInfinite),
+        catchment(&dt, 0)
+    );
+    assert_eq!(
+        Err(startin::StartinError::VertexUnknown),
+        catchment(&dt, 1000)
+    );
+    let vi = dt.closest_point_from(3.0, 3.0, None).unwrap();
+    dt.remove(vi).unwrap();
+    assert_eq!(
+        Err(startin::StartinError::VertexRemoved),
+        catchment(&dt, vi)
+    );
+}
+
+#[test]
+fn catchments_random() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..500 {
+        let x: f64 = rng.gen::<f64>() * 20.0;
+        let y: f64 = rng.gen::<f64>() * 20.0;
+        let _ = dt.insert_one_pt(x, y, (x / 3.0).sin() + (y / 4.0).cos() + rng.gen::<f64>());
+    }
+    let cs = pit_catchments(&dt).unwrap();
+    assert_eq!(pits(&dt).unwrap().len(), cs.len());
+    for c in &cs {
+        //-- the polygons (with their holes) have the area of the cells
+        assert!((polygons_area(c) - c.area).abs() < 1e-9);
+        for p in &c.polygons {
+            assert!(ring_area(&p[0]) > 0.0);
+            for h in &p[1..] {
+                assert!(ring_area(h) < 0.0);
+            }
+        }
+    }
+}
+
+fn with_pit() -> Triangulation {
+    let mut dt = grid(|x, _y| 0.1 * x);
+    let pit = dt.closest_point_from(5.0, 5.0, None).unwrap();
+    dt.update_vertex_z_value(pit, -1.0).unwrap();
+    dt
+}
+
+#[test]
+fn fill() {
+    let mut dt = with_pit();
+    let pit = dt.closest_point_from(5.0, 5.0, None).unwrap();
+    assert_eq!(vec![pit], pits(&dt).unwrap());
+    let re = fill_depressions(&mut dt, 0.0).unwrap();
+    assert_eq!(vec![pit], re.modified);
+    //-- raised to its lowest neighbour (x=4), its cell has an area of 1.0
+    assert!((dt.get_point(pit).unwrap()[2] - 0.4).abs() < 1e-12);
+    assert!((re.volume - 1.4).abs() < 1e-12);
+    //-- with a gradient
+    let mut dt = with_pit();
+    fill_depressions(&mut dt, 0.01).unwrap();
+    assert!((dt.get_point(pit).unwrap()[2] - 0.41).abs() < 1e-12);
+    assert!(pits(&dt).unwrap().is_empty());
+}
+
+#[test]
+fn breach() {
+    let mut dt = with_pit();
+    let pit = dt.closest_point_from(5.0, 5.0, None).unwrap();
+    let re = breach_depressions(&mut dt, 0.0).unwrap();
+    //-- a flat path at -1.0 to the convex hull (x=0)
+    assert_eq!(-1.0, dt.get_point(pit).unwrap()[2]);
+    assert!(re.modified.len() >= 5);
+    for vi in &re.modified {
+        let p = dt.get_point(*vi).unwrap();
+        assert_eq!(-1.0, p[2]);
+        assert!(p[0] < 5.0);
+    }
+    assert!(re.modified.iter().any(|vi| dt.is_vertex_convex_hull(*vi)));
+    assert!(re.volume > 0.0);
+    let mut dt = with_pit();
+    breach_depressions(&mut dt, 0.01).unwrap();
+    assert!(pits(&dt).unwrap().is_empty());
+}
+
+#[test]
+fn depressions_random() {
+    let mut rng = rand::thread_rng();
+    let mut dt = Triangulation::new();
+    for _i in 0..500 {
+        let x: f64 = rng.gen::<f64>() * 20.0;
+        let y: f64 = rng.gen::<f64>() * 20.0;
+        let _ = dt.insert_one_pt(x, y, (x / 3.0).sin() + (y / 4.0).cos() + rng.gen::<f64>());
+    }
+    let zs: Vec<f64> = (1..=500).map(|vi| dt.get_point(vi).unwrap()[2]).collect();
+    let mut filled = Triangulation::new();
+    let mut breached = Triangulation::new();
+    for vi in 1..=500 {
+        let p = dt.get_point(vi).unwrap();
+        let _ = filled.insert_one_pt(p[0], p[1], p[2]);
+        let _ = breached.insert_one_pt(p[0], p[1], p[2]);
+    }
+    let vf = fill_depressions(&mut filled, 1e-4).unwrap();
+    let vb = breach_depressions(&mut breached, 1e-4).unwrap();
+    assert!(pits(&filled).unwrap().is_empty());
+    assert!(pits(&breached).unwrap().is_empty());
+    for vi in 1..=500 {
+        assert!(filled.get_point(vi).unwrap()[2] >= zs[vi - 1]);
+        assert!(breached.get_point(vi).unwrap()[2] <= zs[vi - 1]);
+    }
+    assert!(vf.volume >= 0.0 && vb.volume >= 0.0);
+    //-- the volume is that between the 2 TINs
+    let v0: f64 = dt
+        .all_finite_triangles()
+        .iter()
+        .map(|tr| dt.volume_triangle(tr, 0.0).unwrap())
+        .sum();
+    let v1: f64 = filled
+        .all_finite_triangles()
+        .iter()
+        .map(|tr| filled.volume_triangle(tr, 0.0).unwrap())
+        .sum();
+    assert!((v1 - v0 - vf.volume).abs() < 1e-9);
+}
@@ -1,5 +1,6 @@
 use crate::startin::interpolation::interpolate;
 use crate::startin::Triangulation;
+use assert_approx_eq::assert_approx_eq;
 use rand::prelude::*;
 use startin;
 
@@ -28,80 +29,74 @@ fn random_points_500() -> Triangulation {
 
 #[test]
 fn empty() {
-    let mut dt = startin::Triangulation::new();
+    let dt = startin::Triangulation::new();
     let i_nn = startin::interpolation::NN {};
     let i_tin = startin::interpolation::TIN {};
     let i_lap = startin::interpolation::Laplace {};
     let i_nni = startin::interpolation::NNI { precompute: false };
-    let i_idw = startin::interpolation::IDW {
-        radius: 1.0,
-        power: 2.0,
-    };
+    let i_idw = startin::interpolation::IDW::new(1.0, 2.0);
     assert_eq!(
         Err(startin::StartinError::EmptyTriangulation),
-        startin::interpolation::interpolate(&i_nn, &mut dt, &vec![[51.0, 42.0]])[0]
+        startin::interpolation::interpolate(&i_nn, &dt, &vec![[51.0, 42.0]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::EmptyTriangulation),
-        interpolate(&i_tin, &mut dt, &vec![[51.0, 42.0]])[0]
+        interpolate(&i_tin, &dt, &vec![[51.0, 42.0]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::EmptyTriangulation),
-        interpolate(&i_lap, &mut dt, &vec![[51.0, 42.0]])[0]
+        interpolate(&i_lap, &dt, &vec![[51.0, 42.0]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::EmptyTriangulation),
-        interpolate(&i_nni, &mut dt, &vec![[51.0, 42.0]])[0]
+        interpolate(&i_nni, &dt, &vec![[51.0, 42.0]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::SearchCircleEmpty),
-        interpolate(&i_idw, &mut dt, &vec![[51.0, 42.0]])[0]
+        interpolate(&i_idw, &dt, &vec![[51.0, 42.0]])[0]
     );
 }
 
 #[test]
 fn idw() {
-    let mut dt = four_points();
-    let i_idw = startin::interpolation::IDW {
-        radius: 3.0,
-        power: 2.0,
-    };
+    let dt = four_points();
+    let i_idw = startin::interpolation::IDW::new(3.0, 2.0);
     assert_eq!(
         Err(startin::StartinError::SearchCircleEmpty),
-        startin::interpolation::interpolate(&i_idw, &mut dt, &vec![[5.0, 5.0]])[0]
+        startin::interpolation::interpolate(&i_idw, &dt, &vec![[5.0, 5.0]])[0]
     );
     assert_eq!(
         Ok(3.0),
-        startin::interpolation::interpolate(&i_idw, &mut dt, &vec![[9.0, 9.0]])[0]
+        startin::interpolation::interpolate(&i_idw, &dt, &vec![[9.0, 9.0]])[0]
     );
 }
 
 #[test]
 fn outside_ch() {
-    let mut dt = four_points();
+    let dt = four_points();
     let i_nn = startin::interpolation::NN {};
     let i_tin = startin::interpolation::TIN {};
     let i_lap = startin::interpolation::Laplace {};
     let i_nni = startin::interpolation::NNI { precompute: false };
     assert_eq!(
         Err(startin::StartinError::OutsideConvexHull),
-        startin::interpolation::interpolate(&i_nn, &mut dt, &vec![[5.0, -0.1]])[0]
+        startin::interpolation::interpolate(&i_nn, &dt, &vec![[5.0, -0.1]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::OutsideConvexHull),
-        interpolate(&i_tin, &mut dt, &vec![[5.0, -0.1]])[0]
+        interpolate(&i_tin, &dt, &vec![[5.0, -0.1]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::OutsideConvexHull),
-        interpolate(&i_lap, &mut dt, &vec![[5.0, -0.1]])[0]
+        interpolate(&i_lap, &dt, &vec![[5.0, -0.1]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::OutsideConvexHull),
-        interpolate(&i_nni, &mut dt, &vec![[5.0, -0.1]])[0]
+        interpolate(&i_nni, &dt, &vec![[5.0, -0.1]])[0]
     );
     assert_eq!(
         Err(startin::StartinError::OutsideConvexHull),
-        interpolate(&i_nni, &mut dt, &vec![[5.0, 0.0]])[0]
+        interpolate(&i_nni, &dt, &vec![[5.0, 0.0]])[0]
     );
 }
 
@@ -114,18 +109,15 @@ fn existing_point() {
     let i_tin = startin::interpolation::TIN {};
     let i_lap = startin::interpolation::Laplace {};
     let i_nni = startin::interpolation::NNI { precompute: false };
-    let i_idw = startin::interpolation::IDW {
-        radius: 1.0,
-        power: 2.0,
-    };
+    let i_idw = startin::interpolation::IDW::new(1.0, 2.0);
     assert_eq!(
         Ok(11.1),
-        startin::interpolation::interpolate(&i_nn, &mut dt, &vec![[5.0, 5.0]])[0]
+        startin::interpolation::interpolate(&i_nn, &dt, &vec![[5.0, 5.0]])[0]
     );
-    assert_eq!(Ok(11.1), interpolate(&i_tin, &mut dt, &vec![[5.0, 5.0]])[0]);
-    assert_eq!(Ok(11.1), interpolate(&i_lap, &mut dt, &vec![[5.0, 5.0]])[0]);
-    assert_eq!(Ok(11.1), interpolate(&i_nni, &mut dt, &vec![[5.0, 5.0]])[0]);
-    assert_eq!(Ok(11.1), interpolate(&i_idw, &mut dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(11.1), interpolate(&i_tin, &dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(11.1), interpolate(&i_lap, &dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(11.1), interpolate(&i_nni, &dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(11.1), interpolate(&i_idw, &dt, &vec![[5.0, 5.0]])[0]);
 }
 
 #[test]
@@ -141,21 +133,51 @@ fn existing_point_lowest() {
     let _re = dt.insert_one_pt(5.0, 5.0, 11.1);
 
     let i_lap = startin::interpolation::Laplace {};
-    assert_eq!(Ok(11.1), interpolate(&i_lap, &mut dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(11.1), interpolate(&i_lap, &dt, &vec![[5.0, 5.0]])[0]);
 
     let i_nni = startin::interpolation::NNI { precompute: false };
-    assert_eq!(Ok(11.1), interpolate(&i_nni, &mut dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(11.1), interpolate(&i_nni, &dt, &vec![[5.0, 5.0]])[0]);
 }
 
 #[test]
 fn middle() {
-    let mut dt = four_points();
+    let dt = four_points();
     let i_lap = startin::interpolation::Laplace {};
     let i_nni = startin::interpolation::NNI { precompute: false };
     let i_tin = startin::interpolation::TIN {};
-    assert_eq!(Ok(2.5), interpolate(&i_lap, &mut dt, &vec![[5.0, 5.0]])[0]);
-    assert_eq!(Ok(2.5), interpolate(&i_nni, &mut dt, &vec![[5.0, 5.0]])[0]);
-    assert_eq!(Ok(3.0), interpolate(&i_tin, &mut dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(2.5), interpolate(&i_lap, &dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(2.5), interpolate(&i_nni, &dt, &vec![[5.0, 5.0]])[0]);
+    assert_eq!(Ok(2.0), interpolate(&i_tin, &dt, &vec![[5.0, 5.0]])[0]);
+}
+
+#[test]
+fn shared_triangulation() {
+    let dt = std::sync::Arc::new(four_points());
+    let before = dt.all_triangles();
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let dt = std::sync::Arc::clone(&dt);
+            std::thread::spawn(move || {
+                let locs = vec![[2.0 + i as f64, 3.0], [5.0, 5.0]];
+                let i_nni = startin::interpolation::NNI { precompute: false };
+                let i_lap = startin::interpolation::Laplace {};
+                (
+                    interpolate(&i_nni, &dt, &locs),
+                    interpolate(&i_lap, &dt, &locs),
+                    dt.locate_from(5.0, 5.0, None).is_ok(),
+                    dt.closest_point_from(9.0, 9.0, Some(1)),
+                )
+            })
+        })
+        .collect();
+    for h in handles {
+        let (nni, lap, located, closest) = h.join().unwrap();
+        assert_eq!(Ok(2.5), nni[1]);
+        assert_eq!(Ok(2.5), lap[1]);
+        assert!(located);
+        assert_eq!(Ok(3), closest);
+    }
+    assert_eq!(before, dt.all_triangles());
 }
 
 #[test]
@@ -165,20 +187,567 @@ fn nn() {
     let i_nn = startin::interpolation::NN {};
     assert_eq!(
         Ok(11.1),
-        startin::interpolation::interpolate(&i_nn, &mut dt, &vec![[5.1, 5.1]])[0]
+        startin::interpolation::interpolate(&i_nn, &dt, &vec![[5.1, 5.1]])[0]
     );
 }
 
 #[test]
 fn tin_linear_random() {
-    let mut dt = random_points_500();
+    let dt = random_points_500();
     let i_tin = startin::interpolation::TIN {};
     assert_eq!(
         Err(startin::StartinError::OutsideConvexHull),
-        startin::interpolation::interpolate(&i_tin, &mut dt, &vec![[144.0, 48.0]])[0]
+        startin::interpolation::interpolate(&i_tin, &dt, &vec![[144.0, 48.0]])[0]
     );
     assert_eq!(
         true,
-        startin::interpolation::interpolate(&i_tin, &mut dt, &vec![[44.0, 48.0]])[0].is_ok()
+        startin::interpolation::interpolate(&i_tin, &dt, &vec![[44.0, 48.0]])[0].is_ok()
+    );
+}
+
+#[test]
+fn targets() {
+    let mut dt = four_points();
+    let _ = dt.set_attributes_schema(vec![
+        ("intensity".to_string(), "f64".to_string()),
+        ("class".to_string(), "String".to_string()),
+    ]);
+    for vi in 1..5 {
+        let _ = dt.set_vertex_attribute(
+            vi,
+            "intensity",
+            startin::attributes::AttributeValue::F64(vi as f64 * 10.0),
+        );
+    }
+    let targets = vec![
+        startin::interpolation::Target::Z,
+        startin::interpolation::Target::Attribute("intensity".to_string()),
+    ];
+    let locs = vec![[5.0, 5.0], [0.0, 0.0], [15.0, 5.0]];
+    let i_lap = startin::interpolation::Laplace {};
+    let re = startin::interpolation::interpolate_targets(&i_lap, &dt, &locs, &targets);
+    assert_eq!(Ok(vec![2.5, 25.0]), re[0]);
+    assert_eq!(Ok(vec![1.0, 10.0]), re[1]);
+    assert_eq!(Err(startin::StartinError::OutsideConvexHull), re[2]);
+    let i_tin = startin::interpolation::TIN {};
+    let re = startin::interpolation::interpolate_targets(&i_tin, &dt, &locs, &targets);
+    assert_eq!(Ok(vec![1.0, 10.0]), re[1]);
+    //-- unset values are NaN
+    let _ = dt.insert_one_pt(5.0, 5.0, 5.0);
+    let i_nn = startin::interpolation::NN {};
+    let re = startin::interpolation::interpolate_targets(&i_nn, &dt, &locs, &targets);
+    assert!(re[0].as_ref().unwrap()[1].is_nan());
+    //-- strings cannot be interpolated
+    let re = startin::interpolation::interpolate_targets(
+        &i_nn,
+        &dt,
+        &locs,
+        &[startin::interpolation::Target::Attribute(
+            "class".to_string(),
+        )],
+    );
+    assert_eq!(Err(startin::StartinError::WrongAttribute), re[0]);
+}
+
+/// An interpolant that implements only `interpolate()`
+struct OnlyZ {}
+impl startin::interpolation::Interpolant for OnlyZ {
+    fn interpolate(
+        &self,
+        dt: &Triangulation,
+        locations: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, startin::StartinError>> {
+        interpolate(&startin::interpolation::Laplace {}, dt, locations)
+    }
+}
+
+#[test]
+fn targets_default() {
+    let mut dt = four_points();
+    let _ = dt.set_attributes_schema(vec![("intensity".to_string(), "f64".to_string())]);
+    for vi in 1..5 {
+        let _ = dt.set_vertex_attribute(
+            vi,
+            "intensity",
+            startin::attributes::AttributeValue::F64(vi as f64 * 10.0),
+        );
+    }
+    let targets = vec![
+        startin::interpolation::Target::Attribute("intensity".to_string()),
+        startin::interpolation::Target::Z,
+    ];
+    let locs = vec![[5.0, 5.0], [2.0, 7.0], [15.0, 5.0]];
+    let re = startin::interpolation::interpolate_targets(&OnlyZ {}, &dt, &locs, &targets);
+    let expected = startin::interpolation::interpolate_targets(
+        &startin::interpolation::Laplace {},
+        &dt,
+        &locs,
+        &targets,
+    );
+    assert_eq!(expected, re);
+    assert_eq!(Ok(vec![25.0, 2.5]), re[0]);
+    assert_eq!(Err(startin::StartinError::OutsideConvexHull), re[2]);
+    let re = startin::interpolation::interpolate_targets(
+        &OnlyZ {},
+        &dt,
+        &locs,
+        &[startin::interpolation::Target::Attribute(
+            "unknown".to_string(),
+        )],
+    );
+    assert!(re.iter().all(|r| r.is_err()));
+}
+
+#[test]
+fn weights() {
+    let dt = four_points();
+    let i_nni = startin::interpolation::NNI { precompute: true };
+    let i_tin = startin::interpolation::TIN {};
+    let i_idw = startin::interpolation::IDW::new(20.0, 2.0);
+    let re = startin::interpolation::interpolation_weights(&i_nni, &dt, &vec![[5.0, 5.0]]);
+    let ws = re[0].as_ref().unwrap();
+    assert_eq!(4, ws.len());
+    for (_vi, w) in ws {
+        assert_approx_eq!(0.25, w);
+    }
+    let re = startin::interpolation::interpolation_weights(&i_tin, &dt, &vec![[2.0, 1.0]]);
+    let sum: f64 = re[0].as_ref().unwrap().iter().map(|(_, w)| w).sum();
+    assert_approx_eq!(1.0, sum);
+    let re = startin::interpolation::interpolation_weights(&i_idw, &dt, &vec![[2.0, 1.0]]);
+    let sum: f64 = re[0].as_ref().unwrap().iter().map(|(_, w)| w).sum();
+    assert_approx_eq!(1.0, sum);
+    //-- on a vertex
+    let re = startin::interpolation::interpolation_weights(&i_nni, &dt, &vec![[10.0, 10.0]]);
+    assert_eq!(Ok(vec![(3, 1.0)]), re[0]);
+    let re = startin::interpolation::natural_neighbours(&dt, &vec![[5.0, 5.0], [5.0, -1.0]]);
+    assert_eq!(4, re[0].as_ref().unwrap().len());
+    assert_eq!(Err(startin::StartinError::OutsideConvexHull), re[1]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel() {
+    let dt = random_points_500();
+    let mut rng = rand::thread_rng();
+    let mut locs: Vec<[f64; 2]> = Vec::new();
+    for _i in 0..5000 {
+        locs.push([
+            rng.gen::<f64>() * 110.0 - 5.0,
+            rng.gen::<f64>() * 110.0 - 5.0,
+        ]);
+    }
+    let i_nni = startin::interpolation::NNI { precompute: false };
+    let i_tin = startin::interpolation::TIN {};
+    assert_eq!(
+        interpolate(&i_nni, &dt, &locs),
+        startin::interpolation::par_interpolate(&i_nni, &dt, &locs)
+    );
+    let targets = vec![startin::interpolation::Target::Z];
+    assert_eq!(
+        startin::interpolation::interpolate_targets(&i_tin, &dt, &locs, &targets),
+        startin::interpolation::par_interpolate_targets(&i_tin, &dt, &locs, &targets)
+    );
+}
+
+#[test]
+fn clough_tocher() {
+    //-- a plane is reproduced exactly, whatever the gradient estimation
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..200 {
+        let x: f64 = rng.gen::<f64>() * 100.0;
+        let y: f64 = rng.gen::<f64>() * 100.0;
+        let _re = dt.insert_one_pt(x, y, 2.0 * x - 0.5 * y + 7.0);
+    }
+    let locs: Vec<[f64; 2]> = (0..100)
+        .map(|_| [rng.gen::<f64>() * 100.0, rng.gen::<f64>() * 100.0])
+        .collect();
+    for gradients in [
+        startin::interpolation::GradientEstimation::Normals,
+        startin::interpolation::GradientEstimation::LeastSquares,
+    ] {
+        let i_ct = startin::interpolation::CloughTocher { gradients };
+        let zs = interpolate(&i_ct, &dt, &locs);
+        for (p, z) in locs.iter().zip(zs) {
+            match z {
+                Ok(z) => assert_approx_eq!(2.0 * p[0] - 0.5 * p[1] + 7.0, z, 1e-6),
+                Err(why) => assert_eq!(startin::StartinError::OutsideConvexHull, why),
+            }
+        }
+    }
+    let dt = four_points();
+    let i_ct = startin::interpolation::CloughTocher {
+        gradients: startin::interpolation::GradientEstimation::LeastSquares,
+    };
+    assert_eq!(Ok(3.0), interpolate(&i_ct, &dt, &vec![[10.0, 10.0]])[0]);
+    assert!(interpolate(&i_ct, &dt, &vec![[15.0, 10.0]])[0].is_err());
+}
+
+#[test]
+fn clough_tocher_c1() {
+    //-- on a curved surface, the values and the gradients are the same on both sides of
+    //-- the interior edges, and the derivative at a vertex is linear in the direction
+    let f = |x: f64, y: f64| 10.0 * (x / 15.0).sin() * (y / 20.0).cos() + 0.002 * x * y;
+    let mut dt = Triangulation::new();
+    let mut rng = StdRng::seed_from_u64(33);
+    for _i in 0..100 {
+        let x: f64 = rng.gen::<f64>() * 100.0;
+        let y: f64 = rng.gen::<f64>() * 100.0;
+        let _re = dt.insert_one_pt(x, y, f(x, y));
+    }
+    let d = 1e-6;
+    for gradients in [
+        startin::interpolation::GradientEstimation::Normals,
+        startin::interpolation::GradientEstimation::LeastSquares,
+    ] {
+        let i_ct = startin::interpolation::CloughTocher { gradients };
+        let z = |p: [f64; 2]| interpolate(&i_ct, &dt, &vec![p])[0].clone().unwrap();
+        for tr in dt.all_finite_triangles() {
+            for i in 0..3 {
+                let (a, b) = (tr.v[i], tr.v[(i + 1) % 3]);
+                if dt.is_vertex_convex_hull(a) || dt.is_vertex_convex_hull(b) {
+                    continue;
+                }
+                let (pa, pb) = (dt.get_point(a).unwrap(), dt.get_point(b).unwrap());
+                let m = [(pa[0] + pb[0]) / 2.0, (pa[1] + pb[1]) / 2.0];
+                let len = ((pb[0] - pa[0]).powi(2) + (pb[1] - pa[1]).powi(2)).sqrt();
+                let n = [(pa[1] - pb[1]) / len, (pb[0] - pa[0]) / len];
+                let across = |s: f64| z([m[0] + s * n[0], m[1] + s * n[1]]);
+                assert!((across(d) - across(-d)).abs() < 1e-5);
+                let left = (across(2.0 * d) - across(d)) / d;
+                let right = (across(-d) - across(-2.0 * d)) / d;
+                assert!((left - right).abs() < 1e-3);
+            }
+            for v in tr.v {
+                if dt.is_vertex_convex_hull(v) {
+                    continue;
+                }
+                let p = dt.get_point(v).unwrap();
+                let derivative = |t: f64| (z([p[0] + d * t.cos(), p[1] + d * t.sin()]) - p[2]) / d;
+                let (dx, dy) = (derivative(0.0), derivative(std::f64::consts::FRAC_PI_2));
+                for k in 0..8 {
+                    let t = 0.3 + k as f64 * std::f64::consts::FRAC_PI_4;
+                    assert!((derivative(t) - dx * t.cos() - dy * t.sin()).abs() < 1e-3);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn sibson_c1() {
+    //-- a plane is reproduced exactly, whatever the gradient estimation
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..200 {
+        let x: f64 = rng.gen::<f64>() * 100.0;
+        let y: f64 = rng.gen::<f64>() * 100.0;
+        let _re = dt.insert_one_pt(x, y, 2.0 * x - 0.5 * y + 7.0);
+    }
+    let locs: Vec<[f64; 2]> = (0..100)
+        .map(|_| [rng.gen::<f64>() * 100.0, rng.gen::<f64>() * 100.0])
+        .collect();
+    for gradients in [
+        startin::interpolation::GradientEstimation::Normals,
+        startin::interpolation::GradientEstimation::LeastSquares,
+    ] {
+        let i_sib = startin::interpolation::SibsonC1 { gradients };
+        let zs = interpolate(&i_sib, &dt, &locs);
+        for (p, z) in locs.iter().zip(zs) {
+            match z {
+                Ok(z) => assert_approx_eq!(2.0 * p[0] - 0.5 * p[1] + 7.0, z, 1e-6),
+                Err(why) => assert_eq!(startin::StartinError::OutsideConvexHull, why),
+            }
+        }
+    }
+    //-- a spherical quadric is reproduced if the gradients are exact: on a triangular
+    //-- lattice the 6 neighbours of a vertex are symmetric, so the least-squares ones are
+    let f = |x: f64, y: f64| 0.01 * (x * x + y * y) - 0.3 * x + 2.0;
+    let mut dt = Triangulation::new();
+    for j in 0..11 {
+        for i in 0..11 {
+            let x = (i as f64 + 0.5 * (j % 2) as f64) * 10.0;
+            let y = j as f64 * 10.0 * 3.0_f64.sqrt() / 2.0;
+            let _re = dt.insert_one_pt(x, y, f(x, y));
+        }
+    }
+    let i_sib = startin::interpolation::SibsonC1 {
+        gradients: startin::interpolation::GradientEstimation::LeastSquares,
+    };
+    let i_nni = startin::interpolation::NNI { precompute: false };
+    let locs = vec![[47.0, 41.0], [52.5, 38.3], [61.2, 50.9], [30.1, 22.2]];
+    let zs = interpolate(&i_sib, &dt, &locs);
+    let zs_nni = interpolate(&i_nni, &dt, &locs);
+    for ((p, z), z_nni) in locs.iter().zip(zs).zip(zs_nni) {
+        assert_approx_eq!(f(p[0], p[1]), z.unwrap(), 1e-9);
+        //-- NNI only reproduces the linear functions
+        assert!((f(p[0], p[1]) - z_nni.unwrap()).abs() > 0.1);
+    }
+    let dt = four_points();
+    let i_sib = startin::interpolation::SibsonC1 {
+        gradients: startin::interpolation::GradientEstimation::Normals,
+    };
+    assert_eq!(Ok(3.0), interpolate(&i_sib, &dt, &vec![[10.0, 10.0]])[0]);
+    assert!(interpolate(&i_sib, &dt, &vec![[15.0, 10.0]])[0].is_err());
+}
+
+#[test]
+fn kriging() {
+    let mut dt = Triangulation::new();
+    let mut rng = StdRng::seed_from_u64(35);
+    for _i in 0..500 {
+        let x: f64 = rng.gen::<f64>() * 100.0;
+        let y: f64 = rng.gen::<f64>() * 100.0;
+        let _re = dt.insert_one_pt(x, y, rng.gen::<f64>() * 10.0);
+    }
+    let ev = startin::interpolation::empirical_variogram(
+        &dt,
+        &startin::interpolation::Target::Z,
+        5.0,
+        10,
+    )
+    .unwrap();
+    assert_eq!(10, ev.lags.len());
+    //-- uniform random values: pure nugget, with a variance of 100/12
+    let v = startin::interpolation::Variogram::fit(
+        startin::interpolation::VariogramModel::Spherical,
+        &ev,
+    )
+    .unwrap();
+    assert!((v.nugget + v.sill - 100.0 / 12.0).abs() < 1.5);
+    for neighbourhood in [
+        startin::interpolation::Neighbourhood::KNearest(12),
+        startin::interpolation::Neighbourhood::NaturalNeighbours,
+    ] {
+        let i_ok = startin::interpolation::OrdinaryKriging {
+            variogram: v,
+            neighbourhood,
+        };
+        let vi = 17;
+        let pt = dt.get_point(vi).unwrap();
+        let re = i_ok.interpolate_with_variance(&dt, &vec![[pt[0], pt[1]], [50.0, 50.0]]);
+        assert_eq!(Ok((pt[2], 0.0)), re[0]);
+        let (z, var) = re[1].clone().unwrap();
+        assert!((0.0..=10.0).contains(&z));
+        assert!(var > 0.0);
+        let ws = startin::interpolation::interpolation_weights(&i_ok, &dt, &vec![[50.0, 50.0]]);
+        let sum: f64 = ws[0].as_ref().unwrap().iter().map(|(_, w)| w).sum();
+        assert_approx_eq!(1.0, sum, 1e-9);
+    }
+}
+
+#[test]
+fn rbf() {
+    //-- a plane is reproduced, also outside the convex hull
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..200 {
+        let x: f64 = rng.gen::<f64>() * 100.0;
+        let y: f64 = rng.gen::<f64>() * 100.0;
+        let _re = dt.insert_one_pt(x, y, 0.3 * x + 0.1 * y - 2.0);
+    }
+    let locs = vec![[50.0, 50.0], [25.0, 75.0], [110.0, 50.0], [-5.0, -5.0]];
+    for basis in [
+        startin::interpolation::RadialBasis::ThinPlateSpline,
+        startin::interpolation::RadialBasis::Multiquadric(0.1),
+        startin::interpolation::RadialBasis::Gaussian(0.05),
+    ] {
+        let i_rbf = startin::interpolation::RBF {
+            basis,
+            neighbourhood: startin::interpolation::Neighbourhood::KNearest(10),
+            smoothing: 0.0,
+        };
+        for (p, z) in locs.iter().zip(interpolate(&i_rbf, &dt, &locs)) {
+            assert_approx_eq!(0.3 * p[0] + 0.1 * p[1] - 2.0, z.unwrap(), 1e-6);
+        }
+    }
+    let i_rbf = startin::interpolation::RBF {
+        basis: startin::interpolation::RadialBasis::ThinPlateSpline,
+        neighbourhood: startin::interpolation::Neighbourhood::NaturalNeighbours,
+        smoothing: 0.0,
+    };
+    let re = interpolate(&i_rbf, &dt, &locs);
+    assert_approx_eq!(18.0, re[0].clone().unwrap(), 1e-6);
+    assert_eq!(Err(startin::StartinError::OutsideConvexHull), re[2]);
+}
+
+#[test]
+fn idw_search() {
+    let mut dt = Triangulation::new();
+    for i in 0..10 {
+        for j in 0..10 {
+            let _re = dt.insert_one_pt(i as f64, j as f64, (i + j) as f64);
+        }
+    }
+    //-- removed vertices are ignored, also by the cached index
+    let i_idw = startin::interpolation::IDW::new(1.1, 2.0);
+    let ws = startin::interpolation::interpolation_weights(&i_idw, &dt, &vec![[4.5, 4.5]]);
+    assert_eq!(4, ws[0].as_ref().unwrap().len());
+    let _re = dt.remove(46);
+    let ws = startin::interpolation::interpolation_weights(&i_idw, &dt, &vec![[4.5, 4.5]]);
+    assert_eq!(3, ws[0].as_ref().unwrap().len());
+    let i_idw = startin::interpolation::IDW::default()
+        .with_search(startin::interpolation::IDWSearch::KNearest(3));
+    let ws =
+        startin::interpolation::interpolation_weights(&i_idw, &dt, &vec![[2.2, 7.1], [-1.0, 4.0]]);
+    let mut vs: Vec<usize> = ws[0].as_ref().unwrap().iter().map(|(v, _)| *v).collect();
+    vs.sort();
+    assert_eq!(vec![28, 29, 38], vs);
+    assert!(ws[1].is_ok());
+    //-- quadrants: one vertex per quadrant even if another side is closer
+    let i_idw = startin::interpolation::IDW::new(5.0, 2.0)
+        .with_search(startin::interpolation::IDWSearch::Sectors {
+            sectors: 4,
+            per_sector: 1,
+        })
+        .with_min_neighbours(4);
+    let re =
+        startin::interpolation::interpolation_weights(&i_idw, &dt, &vec![[0.5, 0.5], [-0.5, 0.5]]);
+    assert_eq!(4, re[0].as_ref().unwrap().len());
+    assert_eq!(Err(startin::StartinError::NotEnoughData), re[1]);
+}
+
+#[test]
+fn extrapolation() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    //-- the corners, so that the convex hull is the square
+    for p in [[0.0, 0.0], [100.0, 0.0], [100.0, 100.0], [0.0, 100.0]] {
+        let _re = dt.insert_one_pt(p[0], p[1], 0.5 * p[0] - 0.2 * p[1] + 4.0);
+    }
+    for _i in 0..100 {
+        let x: f64 = rng.gen::<f64>() * 100.0;
+        let y: f64 = rng.gen::<f64>() * 100.0;
+        let _re = dt.insert_one_pt(x, y, 0.5 * x - 0.2 * y + 4.0);
+    }
+    let locs = vec![[50.0, 50.0], [105.0, 50.0], [50.0, -20.0]];
+    let e_tin = startin::interpolation::Extrapolating {
+        interpolant: startin::interpolation::TIN {},
+        method: startin::interpolation::ExtrapolationMethod::PlanarExtension,
+        max_distance: 10.0,
+    };
+    let re = e_tin.interpolate_flagged(&dt, &locs);
+    assert!(!re[0].as_ref().unwrap().is_extrapolated());
+    assert!(re[1].as_ref().unwrap().is_extrapolated());
+    assert_approx_eq!(
+        0.5 * 105.0 - 0.2 * 50.0 + 4.0,
+        re[1].as_ref().unwrap().value(),
+        1e-9
+    );
+    assert_eq!(Err(startin::StartinError::OutsideConvexHull), re[2]);
+    //-- on the closest hull edge the values are those of the TIN
+    let e_nni = startin::interpolation::Extrapolating {
+        interpolant: startin::interpolation::NNI { precompute: false },
+        method: startin::interpolation::ExtrapolationMethod::HullEdgeProjection,
+        max_distance: f64::INFINITY,
+    };
+    let re = interpolate(&e_nni, &dt, &locs);
+    assert!(re.iter().all(|r| r.is_ok()));
+    let four = four_points();
+    let e_nn = startin::interpolation::Extrapolating {
+        interpolant: startin::interpolation::NN {},
+        method: startin::interpolation::ExtrapolationMethod::NearestNeighbour,
+        max_distance: f64::INFINITY,
+    };
+    let re = e_nn.interpolate_flagged(&four, &vec![[12.0, 11.0], [-1.0, 3.0]]);
+    assert_eq!(
+        Ok(startin::interpolation::Estimate::Extrapolated(3.0)),
+        re[0]
+    );
+    assert_eq!(
+        Ok(startin::interpolation::Estimate::Extrapolated(1.0)),
+        re[1]
+    );
+    //-- no convex hull to extrapolate from
+    let e_out = startin::interpolation::Extrapolating {
+        interpolant: AlwaysOutside {},
+        method: startin::interpolation::ExtrapolationMethod::NearestNeighbour,
+        max_distance: f64::INFINITY,
+    };
+    let mut two = Triangulation::new();
+    let _re = two.insert_one_pt(0.0, 0.0, 1.0);
+    let _re = two.insert_one_pt(1.0, 0.0, 2.0);
+    for dt in [Triangulation::new(), two] {
+        assert_eq!(
+            vec![Err(startin::StartinError::EmptyTriangulation)],
+            e_out.interpolate_flagged(&dt, &vec![[0.5, 0.5]])
+        );
+    }
+}
+
+/// An interpolant for which all the locations are outside the convex hull
+struct AlwaysOutside {}
+impl startin::interpolation::Interpolant for AlwaysOutside {
+    fn interpolate(
+        &self,
+        _dt: &Triangulation,
+        locations: &Vec<[f64; 2]>,
+    ) -> Vec<Result<f64, startin::StartinError>> {
+        locations
+            .iter()
+            .map(|_| Err(startin::StartinError::OutsideConvexHull))
+            .collect()
+    }
+}
+
+#[test]
+fn cross_validation() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..200 {
+        let x: f64 = rng.gen::<f64>() * 100.0;
+        let y: f64 = rng.gen::<f64>() * 100.0;
+        let _re = dt.insert_one_pt(x, y, 0.5 * x - 0.2 * y + 4.0);
+    }
+    let nbv = dt.number_of_vertices();
+    let nbch = dt.number_of_vertices_on_convex_hull();
+    //-- a plane is reproduced by TIN, the vertices of the convex hull can't be interpolated
+    let cv = startin::interpolation::cross_validation(&startin::interpolation::TIN {}, &dt, None)
+        .unwrap();
+    assert_eq!(nbv, cv.residuals.len());
+    assert_eq!(nbv - nbch, cv.count);
+    assert!(cv.rmse < 1e-9);
+    assert!(cv.max < 1e-9);
+    for (vi, r) in &cv.residuals {
+        if dt.is_vertex_convex_hull(*vi) {
+            assert_eq!(Err(startin::StartinError::OutsideConvexHull), *r);
+        }
+    }
+    //-- the triangulation is not modified
+    assert_eq!(nbv, dt.number_of_vertices());
+    assert_eq!(nbch, dt.number_of_vertices_on_convex_hull());
+    //-- the spatial index used by IDW ignores the vertex removed (with a radius large enough
+    //-- so that the vertices at the corners have neighbours)
+    let i_idw = startin::interpolation::IDW::new(30.0, 2.0);
+    let cv_idw = startin::interpolation::cross_validation(&i_idw, &dt, None).unwrap();
+    assert!(cv_idw
+        .residuals
+        .iter()
+        .all(|(_, r)| r.as_ref().is_ok_and(|e| e.is_finite())));
+    //-- same residual as removing the vertex by hand
+    let vi = cv.residuals.iter().find(|(_, r)| r.is_ok()).unwrap().0;
+    let i_nn = startin::interpolation::NN {};
+    let cv = startin::interpolation::cross_validation(&i_nn, &dt, Some(&[vi])).unwrap();
+    let p = dt.get_point(vi).unwrap();
+    dt.remove(vi).unwrap();
+    let z = interpolate(&i_nn, &dt, &vec![[p[0], p[1]]])[0]
+        .clone()
+        .unwrap();
+    let z_idw = interpolate(&i_idw, &dt, &vec![[p[0], p[1]]])[0]
+        .clone()
+        .unwrap();
+    let r_idw = cv_idw.residuals.iter().find(|(v, _)| *v == vi).unwrap();
+    assert_approx_eq!(z_idw - p[2], r_idw.1.clone().unwrap(), 1e-12);
+    assert_eq!(1, cv.count);
+    assert_approx_eq!(z - p[2], cv.residuals[0].1.clone().unwrap(), 1e-12);
+    assert_approx_eq!((z - p[2]).abs(), cv.rmse, 1e-12);
+    assert_eq!(
+        Err(startin::StartinError::VertexRemoved),
+        startin::interpolation::cross_validation(&i_nn, &dt, Some(&[vi])).map(|cv| cv.count)
+    );
+    assert_eq!(
+        Err(startin::StartinError::EmptyTriangulation),
+        startin::interpolation::cross_validation(&i_nn, &Triangulation::new(), None)
+            .map(|cv| cv.count)
     );
 }
@@ -0,0 +1,102 @@
+mod common;
+use common::grid;
+
+use assert_approx_eq::assert_approx_eq;
+use rand::prelude::*;
+use startin::interpolation::{interpolate, TIN};
+use startin::profile::profile;
+use startin::Triangulation;
+
+#[test]
+fn plane() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..300 {
+        let x: f64 = rng.gen::<f64>() * 20.0;
+        let y: f64 = rng.gen::<f64>() * 20.0;
+        let _ = dt.insert_one_pt(x, y, 0.5 * x - 0.2 * y);
+    }
+    let line = [[5.0, 5.0], [15.0, 12.0], [8.0, 14.0]];
+    let pts = profile(&dt, &line).unwrap();
+    assert_eq!([5.0, 5.0], [pts[0].x, pts[0].y]);
+    assert_eq!([8.0, 14.0], [pts.last().unwrap().x, pts.last().unwrap().y]);
+    let length = 149.0_f64.sqrt() + 53.0_f64.sqrt();
+    assert_approx_eq!(length, pts.last().unwrap().distance, 1e-9);
+    for w in pts.windows(2) {
+        assert!(w[1].distance > w[0].distance);
+    }
+    for p in &pts {
+        assert_approx_eq!(0.5 * p.x - 0.2 * p.y, p.z, 1e-9);
+    }
+    //-- at least one crossing per triangle
+    assert!(pts.len() > 10);
+    for _i in 0..50 {
+        let a = [5.0 + rng.gen::<f64>() * 10.0, 5.0 + rng.gen::<f64>() * 10.0];
+        let b = [5.0 + rng.gen::<f64>() * 10.0, 5.0 + rng.gen::<f64>() * 10.0];
+        let pts = profile(&dt, &[a, b]).unwrap();
+        for p in &pts {
+            assert_approx_eq!(0.5 * p.x - 0.2 * p.y, p.z, 1e-9);
+        }
+    }
+}
+
+#[test]
+fn crossings() {
+    let dt = grid(|x, y| x * y);
+    //-- along a row of vertices: every vertex, and nothing else
+    let pts = profile(&dt, &[[0.5, 3.0], [9.5, 3.0]]).unwrap();
+    assert_eq!(11, pts.len());
+    for (i, p) in pts[1..10].iter().enumerate() {
+        assert_eq!(
+            ((i + 1) as f64 - 0.5, 3.0 * (i + 1) as f64),
+            (p.distance, p.z)
+        );
+        assert!(p.vertex.is_some());
+    }
+    assert_eq!((None, None), (pts[0].vertex, pts[10].vertex));
+    //-- along the convex hull, ending on an edge
+    let pts = profile(&dt, &[[10.0, 0.0], [10.0, 4.5]]).unwrap();
+    assert_eq!(6, pts.len());
+    assert_eq!(
+        (4.5, 45.0, None),
+        (pts[5].distance, pts[5].z, pts[5].vertex)
+    );
+    //-- between the rows, the z-values are exact for the TIN
+    let pts = profile(&dt, &[[0.0, 2.5], [10.0, 2.5]]).unwrap();
+    assert_eq!(0.0, pts[0].distance);
+    assert_eq!(None, pts.last().unwrap().vertex);
+    let locs: Vec<[f64; 2]> = pts.iter().map(|p| [p.x, p.y]).collect();
+    let zs = interpolate(&TIN {}, &dt, &locs);
+    for (p, z) in pts.iter().zip(zs) {
+        assert_approx_eq!(z.unwrap(), p.z, 1e-9);
+    }
+    //-- the vertical edges and the diagonals are crossed
+    assert!(pts.len() >= 21);
+    //-- ending on a vertex of the triangle of the start, or reached through another vertex
+    let v = dt.closest_point_from(3.0, 3.0, None).unwrap();
+    for line in [[[3.2, 3.1], [3.0, 3.0]], [[1.0, 3.0], [3.0, 3.0]]] {
+        let pts = profile(&dt, &line).unwrap();
+        assert_eq!(
+            (Some(v), 9.0),
+            (pts.last().unwrap().vertex, pts.last().unwrap().z)
+        );
+    }
+}
+
+#[test]
+fn errors() {
+    let dt = Triangulation::new();
+    assert_eq!(
+        Err(startin::StartinError::EmptyTriangulation),
+        profile(&dt, &[[0.0, 0.0], [1.0, 1.0]])
+    );
+    let dt = grid(|x, _y| x);
+    assert_eq!(
+        Err(startin::StartinError::OutsideConvexHull),
+        profile(&dt, &[[1.0, 1.0], [11.0, 1.0]])
+    );
+    //-- a single point
+    let pts = profile(&dt, &[[2.0, 2.0]]).unwrap();
+    assert_eq!(1, pts.len());
+    assert_eq!(Some(2.0), pts.first().map(|p| p.z));
+}
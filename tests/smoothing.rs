@@ -0,0 +1,200 @@
+mod common;
+use common::jittered_grid;
+
+use rand::prelude::*;
+use startin::attributes::AttributeValue;
+use startin::smoothing::{smooth_z, Smoothing, SmoothingMethod};
+use startin::Triangulation;
+
+fn max_deviation(dt: &Triangulation, f: impl Fn(f64, f64) -> f64) -> f64 {
+    let mut m: f64 = 0.0;
+    for vi in 1..=dt.number_of_vertices() {
+        let p = dt.get_point(vi).unwrap();
+        m = m.max((p[2] - f(p[0], p[1])).abs());
+    }
+    m
+}
+
+/// Root-mean-square deviation of the vertices far from the boundary
+fn rms_deviation_centre(dt: &Triangulation, f: impl Fn(f64, f64) -> f64) -> f64 {
+    let mut sum = 0.0;
+    let mut n = 0;
+    for vi in 1..=dt.number_of_vertices() {
+        let p = dt.get_point(vi).unwrap();
+        if (3.0..16.0).contains(&p[0]) && (3.0..16.0).contains(&p[1]) {
+            sum += (p[2] - f(p[0], p[1])).powi(2);
+            n += 1;
+        }
+    }
+    (sum / n as f64).sqrt()
+}
+
+#[test]
+fn empty() {
+    let mut dt = Triangulation::new();
+    let s = Smoothing {
+        method: SmoothingMethod::Laplacian { lambda: 0.5 },
+        iterations: 1,
+        lock_convex_hull: false,
+        lock_attribute: None,
+    };
+    assert_eq!(
+        Err(startin::StartinError::EmptyTriangulation),
+        smooth_z(&mut dt, &s)
+    );
+}
+
+#[test]
+fn laplacian_noise() {
+    let plane = |x: f64, y: f64| 0.2 * x + 0.1 * y;
+    let mut rng = rand::thread_rng();
+    let mut dt = jittered_grid(20, |x, y| plane(x, y) + (rng.gen::<f64>() - 0.5) * 0.4);
+    let nb = dt.number_of_vertices();
+    let before = rms_deviation_centre(&dt, plane);
+    for method in [
+        SmoothingMethod::Laplacian { lambda: 0.5 },
+        SmoothingMethod::Taubin {
+            lambda: 0.5,
+            mu: -0.53,
+        },
+    ] {
+        let mut dt2 = jittered_grid(20, |x, y| plane(x, y) + (rng.gen::<f64>() - 0.5) * 0.4);
+        let s = Smoothing {
+            method,
+            iterations: 10,
+            lock_convex_hull: true,
+            lock_attribute: None,
+        };
+        let nbch = dt2.number_of_vertices_on_convex_hull();
+        let ch: Vec<Vec<f64>> = dt2
+            .convex_hull()
+            .iter()
+            .map(|vi| dt2.get_point(*vi).unwrap())
+            .collect();
+        assert_eq!(Ok(nb - nbch), smooth_z(&mut dt2, &s));
+        //-- the convex hull is locked
+        for (vi, p) in dt2.convex_hull().iter().zip(ch) {
+            assert_eq!(p, dt2.get_point(*vi).unwrap());
+        }
+    }
+    let s = Smoothing {
+        method: SmoothingMethod::Laplacian { lambda: 0.5 },
+        iterations: 10,
+        lock_convex_hull: true,
+        lock_attribute: None,
+    };
+    let _ = smooth_z(&mut dt, &s);
+    assert!(rms_deviation_centre(&dt, plane) < 0.5 * before);
+}
+
+#[test]
+fn taubin_shrinks_less() {
+    let bump = |x: f64, y: f64| 5.0 * (-((x - 10.0).powi(2) + (y - 10.0).powi(2)) / 8.0).exp();
+    let mut peaks: Vec<f64> = Vec::new();
+    for method in [
+        SmoothingMethod::Laplacian { lambda: 0.5 },
+        SmoothingMethod::Taubin {
+            lambda: 0.5,
+            mu: -0.53,
+        },
+    ] {
+        let mut dt = jittered_grid(20, bump);
+        let s = Smoothing {
+            method,
+            iterations: 20,
+            lock_convex_hull: false,
+            lock_attribute: None,
+        };
+        let _ = smooth_z(&mut dt, &s);
+        let vi = dt.closest_point(10.0, 10.0).unwrap();
+        peaks.push(dt.get_point(vi).unwrap()[2]);
+    }
+    assert!(peaks[1] > peaks[0]);
+}
+
+#[test]
+fn bilateral() {
+    //-- a plane is preserved
+    let plane = |x: f64, y: f64| 0.3 * x - 0.4 * y + 2.0;
+    let mut dt = jittered_grid(20, plane);
+    let s = Smoothing {
+        method: SmoothingMethod::Bilateral {
+            sigma_distance: 1.0,
+            sigma_normal: 0.2,
+        },
+        iterations: 5,
+        lock_convex_hull: false,
+        lock_attribute: None,
+    };
+    let _ = smooth_z(&mut dt, &s);
+    assert!(max_deviation(&dt, plane) < 1e-9);
+    //-- and the noise is reduced
+    let mut rng = rand::thread_rng();
+    let mut dt = jittered_grid(20, |x, y| plane(x, y) + (rng.gen::<f64>() - 0.5) * 0.4);
+    let before = rms_deviation_centre(&dt, plane);
+    let s = Smoothing {
+        method: SmoothingMethod::Bilateral {
+            sigma_distance: 1.0,
+            sigma_normal: 0.5,
+        },
+        iterations: 10,
+        lock_convex_hull: false,
+        lock_attribute: None,
+    };
+    let _ = smooth_z(&mut dt, &s);
+    assert!(rms_deviation_centre(&dt, plane) < 0.5 * before);
+    //-- a step (a breakline) is preserved better than with the Laplacian
+    let step = |x: f64, _y: f64| if x < 9.5 { 0.0 } else { 5.0 };
+    let mut errs: Vec<f64> = Vec::new();
+    for method in [
+        SmoothingMethod::Laplacian { lambda: 0.5 },
+        SmoothingMethod::Bilateral {
+            sigma_distance: 1.0,
+            sigma_normal: 0.2,
+        },
+    ] {
+        let mut dt = jittered_grid(20, step);
+        let s = Smoothing {
+            method,
+            iterations: 5,
+            lock_convex_hull: false,
+            lock_attribute: None,
+        };
+        let _ = smooth_z(&mut dt, &s);
+        errs.push(max_deviation(&dt, step));
+    }
+    assert!(errs[1] < 0.1 * errs[0]);
+}
+
+#[test]
+fn lock_attribute() {
+    let mut dt = Triangulation::new();
+    let _ = dt.set_attributes_schema(vec![("ground".to_string(), "bool".to_string())]);
+    let pts: Vec<[f64; 3]> = vec![
+        [0.0, 0.0, 0.0],
+        [10.0, 0.0, 0.0],
+        [10.0, 10.0, 0.0],
+        [0.0, 10.0, 0.0],
+        [5.0, 5.0, 10.0],
+        [3.0, 6.0, 10.0],
+    ];
+    dt.insert(&pts, startin::InsertionStrategy::AsIs);
+    let _ = dt.set_vertex_attribute(5, "ground", AttributeValue::Bool(true));
+    let s = Smoothing {
+        method: SmoothingMethod::Laplacian { lambda: 1.0 },
+        iterations: 1,
+        lock_convex_hull: true,
+        lock_attribute: Some(("ground".to_string(), AttributeValue::Bool(true))),
+    };
+    assert_eq!(Ok(1), smooth_z(&mut dt, &s));
+    assert_eq!(10.0, dt.get_point(5).unwrap()[2]);
+    assert!(dt.get_point(6).unwrap()[2] < 10.0);
+    let s = Smoothing {
+        lock_attribute: Some(("classification".to_string(), AttributeValue::U64(2))),
+        ..s
+    };
+    assert_eq!(
+        Err(startin::StartinError::WrongAttribute),
+        smooth_z(&mut dt, &s)
+    );
+}
@@ -0,0 +1,152 @@
+mod common;
+use common::random_surface;
+
+use assert_approx_eq::assert_approx_eq;
+use startin::interpolation::Grid;
+use startin::terrain::*;
+
+#[test]
+fn slope_aspect() {
+    //-- the terrain goes down towards the west
+    let dt = random_surface([-10.0, -10.0, 10.0, 10.0], 300, |x, _y| 0.5 * x);
+    let tr = dt.locate_from(0.0, 0.0, None).unwrap();
+    assert_approx_eq!(
+        0.5_f64.atan().to_degrees(),
+        slope_triangle(&dt, &tr, SlopeUnit::Degrees).unwrap(),
+        1e-9
+    );
+    assert_approx_eq!(
+        50.0,
+        slope_triangle(&dt, &tr, SlopeUnit::Percent).unwrap(),
+        1e-9
+    );
+    assert_approx_eq!(270.0, aspect_triangle(&dt, &tr).unwrap(), 1e-9);
+    assert_approx_eq!(
+        50.0,
+        slope_vertex(&dt, 1, SlopeUnit::Percent).unwrap(),
+        1e-9
+    );
+    assert_approx_eq!(270.0, aspect_vertex(&dt, 1).unwrap(), 1e-9);
+    //-- towards the north-east
+    let dt = random_surface([-10.0, -10.0, 10.0, 10.0], 300, |x, y| -x - y);
+    assert_approx_eq!(45.0, aspect_vertex(&dt, 1).unwrap(), 1e-9);
+    //-- flat
+    let dt = random_surface([-10.0, -10.0, 10.0, 10.0], 300, |_x, _y| 2.0);
+    assert_eq!(0.0, slope_vertex(&dt, 1, SlopeUnit::Degrees).unwrap());
+    assert!(aspect_vertex(&dt, 1).unwrap().is_nan());
+    assert_eq!(
+        Err(startin::StartinError::VertexInfinite),
+        slope_vertex(&dt, 0, SlopeUnit::Degrees)
+    );
+}
+
+#[test]
+fn curvatures() {
+    //-- a paraboloid (a hill), the quadratic fitted is exact
+    let dt = random_surface([-10.0, -10.0, 10.0, 10.0], 300, |x, y| {
+        -(x * x + y * y) / 20.0
+    });
+    for vi in 1..=dt.number_of_vertices() {
+        if dt.is_vertex_convex_hull(vi) {
+            continue;
+        }
+        let p = dt.get_point(vi).unwrap();
+        let g = (p[0] * p[0] + p[1] * p[1]).sqrt() / 10.0;
+        let c = curvature_vertex(&dt, vi).unwrap();
+        assert_approx_eq!(
+            (1.0 + g * g / 2.0) * 0.1 / (1.0 + g * g).powf(1.5),
+            c.mean,
+            1e-6
+        );
+        //-- the contour lines are circles
+        assert_approx_eq!(1.0 / (10.0 * g), c.plan, 1e-6 / g);
+        assert_approx_eq!(0.1 / (1.0 + g * g).powf(1.5), c.profile, 1e-6);
+    }
+    //-- a plane has no curvature
+    let dt = random_surface([-10.0, -10.0, 10.0, 10.0], 300, |x, y| 0.3 * x - 0.1 * y);
+    let c = curvature_vertex(&dt, 5).unwrap();
+    assert_approx_eq!(0.0, c.plan, 1e-9);
+    assert_approx_eq!(0.0, c.profile, 1e-9);
+    assert_approx_eq!(0.0, c.mean, 1e-9);
+}
+
+#[test]
+fn store_as_attributes() {
+    let mut dt = random_surface([-10.0, -10.0, 10.0, 10.0], 300, |x, y| {
+        0.3 * x - 0.1 * y + 0.01 * x * y
+    });
+    let ds = [
+        Derivative::Slope(SlopeUnit::Degrees),
+        Derivative::Slope(SlopeUnit::Percent),
+        Derivative::Aspect,
+        Derivative::MeanCurvature,
+    ];
+    store_vertex_derivatives(&mut dt, &ds).unwrap();
+    assert_eq!(4, dt.get_attributes_schema().len());
+    for vi in [1, 10, 100] {
+        assert_eq!(
+            Some(slope_vertex(&dt, vi, SlopeUnit::Degrees).unwrap()),
+            dt.get_vertex_attribute_f64(vi, "slope_deg").unwrap()
+        );
+        assert_eq!(
+            Some(slope_vertex(&dt, vi, SlopeUnit::Percent).unwrap()),
+            dt.get_vertex_attribute_f64(vi, "slope_pct").unwrap()
+        );
+        assert_eq!(
+            Some(curvature_vertex(&dt, vi).unwrap().mean),
+            dt.get_vertex_attribute_f64(vi, "mean_curvature").unwrap()
+        );
+    }
+    store_triangle_derivatives(&mut dt, &ds).unwrap();
+    let tr = dt.locate_from(1.0, 1.0, None).unwrap();
+    //-- the vertices of the triangle can be in another order, so the rounding can differ
+    let stored = dt.get_triangle_attribute(&tr, "aspect").unwrap();
+    assert_approx_eq!(
+        aspect_triangle(&dt, &tr).unwrap(),
+        stored.unwrap().as_f64().unwrap(),
+        1e-9
+    );
+    let mean: f64 =
+        tr.v.iter()
+            .map(|vi| curvature_vertex(&dt, *vi).unwrap().mean)
+            .sum::<f64>()
+            / 3.0;
+    let stored = dt.get_triangle_attribute(&tr, "mean_curvature").unwrap();
+    assert_approx_eq!(mean, stored.unwrap().as_f64().unwrap(), 1e-12);
+}
+
+#[test]
+fn grid() {
+    let dt = random_surface([-10.0, -10.0, 10.0, 10.0], 300, |x, y| 0.5 * x + 0.5 * y);
+    let grid = Grid {
+        origin: [-5.0, -5.0],
+        cellsize: 1.0,
+        ncols: 10,
+        nrows: 8,
+    };
+    let centres = grid.cell_centres();
+    assert_eq!(80, centres.len());
+    assert_eq!([-4.5, 2.5], centres[0]);
+    assert_eq!([4.5, -4.5], centres[79]);
+    let re = derivative_grid(&dt, Derivative::Slope(SlopeUnit::Percent), &grid);
+    for r in &re {
+        assert_approx_eq!(50.0 * 2.0_f64.sqrt(), r.as_ref().unwrap(), 1e-9);
+    }
+    let re = derivative_grid(&dt, Derivative::Aspect, &grid);
+    for r in &re {
+        assert_approx_eq!(225.0, r.as_ref().unwrap(), 1e-9);
+    }
+    //-- outside the convex hull
+    let g2 = Grid::from_bbox(&[20.0, 20.0, 22.0, 21.0], 1.0);
+    assert_eq!((2, 1), (g2.ncols, g2.nrows));
+    let re = derivative_grid(&dt, Derivative::MeanCurvature, &g2);
+    assert_eq!(Err(startin::StartinError::OutsideConvexHull), re[0]);
+    //-- raster output
+    let path = std::env::temp_dir().join("startin_terrain_grid.asc");
+    g2.write_asc(path.to_str().unwrap().to_string(), &re, -9999.0)
+        .unwrap();
+    let s = std::fs::read_to_string(&path).unwrap();
+    assert!(s.starts_with("ncols 2\nnrows 1\nxllcorner 20\nyllcorner 20\ncellsize 1\n"));
+    assert!(s.ends_with("NODATA_value -9999\n-9999 -9999\n"));
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,180 @@
+mod common;
+use common::grid;
+
+use rand::prelude::*;
+use startin::visibility::*;
+use startin::Triangulation;
+
+#[test]
+fn line_of_sight_wall() {
+    //-- a wall of height 10 at x=5
+    let dt = grid(|x, _y| if x == 5.0 { 10.0 } else { 0.0 });
+    assert!(!line_of_sight(&dt, &[1.0, 1.5, 2.0], &[9.0, 8.5, 2.0]).unwrap());
+    assert!(line_of_sight(&dt, &[1.0, 1.5, 12.0], &[9.0, 8.5, 12.0]).unwrap());
+    //-- touching the top of the wall
+    assert!(line_of_sight(&dt, &[1.0, 2.0, 2.0], &[9.0, 2.0, 18.0]).unwrap());
+    assert!(!line_of_sight(&dt, &[1.0, 2.0, 2.0], &[9.0, 2.0, 17.9]).unwrap());
+    //-- on the same side
+    assert!(line_of_sight(&dt, &[0.0, 0.0, 0.0], &[4.0, 10.0, 0.0]).unwrap());
+    assert_eq!(
+        Err(startin::StartinError::OutsideConvexHull),
+        line_of_sight(&dt, &[1.0, 1.0, 2.0], &[12.0, 1.0, 2.0])
+    );
+}
+
+#[test]
+fn viewshed_wall() {
+    let dt = grid(|x, _y| if x == 5.0 { 10.0 } else { 0.0 });
+    let vs = viewshed(&dt, 2.5, 5.0, 1.7).unwrap();
+    assert_eq!([2.5, 5.0, 1.7], vs.observer);
+    //-- the west half and the wall
+    assert_eq!(66, vs.visible_vertices().len());
+    for vi in vs.visible_vertices() {
+        assert!(dt.get_point(vi).unwrap()[0] <= 5.0);
+    }
+    assert!(!vs.vertices[0]);
+    assert_eq!(100, vs.visible_triangles().len());
+    assert_eq!(50.0, vs.visible_area(&dt));
+    //-- above the wall, everything is visible
+    let vs = viewshed(&dt, 2.5, 5.0, 50.0).unwrap();
+    assert_eq!(121, vs.visible_vertices().len());
+    assert_eq!(100.0, vs.visible_area(&dt));
+}
+
+#[test]
+fn viewshed_convex() {
+    //-- everything is visible on a convex (bowl) surface
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..300 {
+        let x: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let y: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let _ = dt.insert_one_pt(x, y, (x * x + y * y) / 10.0);
+    }
+    let vs = viewshed(&dt, 1.0, -2.0, 0.0).unwrap();
+    assert_eq!(dt.number_of_vertices(), vs.visible_vertices().len());
+    //-- on a hill the other side is hidden
+    let mut dt = Triangulation::new();
+    for _i in 0..300 {
+        let x: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let y: f64 = rng.gen::<f64>() * 20.0 - 10.0;
+        let _ = dt.insert_one_pt(x, y, -(x * x + y * y) / 10.0);
+    }
+    let vs = viewshed(&dt, -8.0, 0.0, 0.1).unwrap();
+    for vi in vs.visible_vertices() {
+        assert!(dt.get_point(vi).unwrap()[0] < 0.0);
+    }
+}
+
+fn inside(dt: &Triangulation, tr: &startin::Triangle, p: &[f64; 3]) -> bool {
+    let v: Vec<Vec<f64>> = tr.v.iter().map(|v| dt.get_point(*v).unwrap()).collect();
+    (0..3).all(|i| {
+        let (a, b) = (&v[i], &v[(i + 1) % 3]);
+        (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0]) > -1e-9
+    })
+}
+
+#[test]
+fn rays() {
+    let mut dt = Triangulation::new();
+    let mut rng = rand::thread_rng();
+    for _i in 0..300 {
+        let x: f64 = rng.gen::<f64>() * 20.0;
+        let y: f64 = rng.gen::<f64>() * 20.0;
+        let _ = dt.insert_one_pt(x, y, 0.1 * x + 0.2 * y);
+    }
+    let mut rays: Vec<([f64; 3], [f64; 3])> = Vec::new();
+    while rays.len() < 100 {
+        let o = [
+            5.0 + rng.gen::<f64>() * 10.0,
+            5.0 + rng.gen::<f64>() * 10.0,
+            20.0,
+        ];
+        let d = [
+            rng.gen::<f64>() - 0.5,
+            rng.gen::<f64>() - 0.5,
+            -rng.gen::<f64>(),
+        ];
+        //-- the analytical intersection with the plane
+        let s = (o[2] - 0.1 * o[0] - 0.2 * o[1]) / (0.1 * d[0] + 0.2 * d[1] - d[2]);
+        let p = [o[0] + s * d[0], o[1] + s * d[1], o[2] + s * d[2]];
+        let h = intersect_ray(&dt, &o, &d).unwrap();
+        if p[0] < 2.0 || p[0] > 18.0 || p[1] < 2.0 || p[1] > 18.0 {
+            continue;
+        }
+        let h = h.unwrap();
+        for (a, b) in p.iter().zip(h.point) {
+            assert!((a - b).abs() < 1e-9);
+        }
+        let dist = s * (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+        assert!((dist - h.distance).abs() < 1e-9);
+        assert!(dt.is_triangle(&h.triangle));
+        assert!(inside(&dt, &h.triangle, &h.point));
+        rays.push((o, d));
+    }
+    //-- the batch version gives the same hits
+    let hits = intersect_rays(&dt, &rays);
+    for (r, h) in rays.iter().zip(hits) {
+        let h1 = intersect_ray(&dt, &r.0, &r.1).unwrap().unwrap();
+        let h2 = h.unwrap().unwrap();
+        //-- the walk starts elsewhere, so the rounding can differ
+        for (a, b) in h1.point.iter().zip(h2.point) {
+            assert!((a - b).abs() < 1e-9);
+        }
+        assert!((h1.distance - h2.distance).abs() < 1e-9);
+        //-- the triangle can start at another vertex
+        let (mut v1, mut v2) = (h1.triangle.v, h2.triangle.v);
+        v1.sort();
+        v2.sort();
+        assert_eq!(v1, v2);
+    }
+    //-- a ray going up, and a horizontal ray, leave the convex hull
+    assert_eq!(
+        Ok(None),
+        intersect_ray(&dt, &[10.0, 10.0, 20.0], &[1.0, 0.0, 0.5])
+    );
+    assert_eq!(
+        Ok(None),
+        intersect_ray(&dt, &[10.0, 10.0, 20.0], &[1.0, 1.0, 0.0])
+    );
+    //-- a vertical ray
+    let h = intersect_ray(&dt, &[10.0, 10.0, 20.0], &[0.0, 0.0, -2.0])
+        .unwrap()
+        .unwrap();
+    assert!((h.point[2] - 3.0).abs() < 1e-9 && (h.distance - 17.0).abs() < 1e-9);
+    //-- the origin below the surface
+    let h = intersect_ray(&dt, &[10.0, 10.0, 0.0], &[1.0, 0.0, 1.0]);
+    assert_eq!(0.0, h.unwrap().unwrap().distance);
+    assert_eq!(
+        Err(startin::StartinError::OutsideConvexHull),
+        intersect_ray(&dt, &[30.0, 10.0, 20.0], &[-1.0, 0.0, -1.0])
+    );
+}
+
+#[test]
+fn rays_wall() {
+    //-- the ray hits the side of the wall at x=5 (between x=4 and x=5)
+    let dt = grid(|x, _y| if x == 5.0 { 10.0 } else { 0.0 });
+    let h = intersect_ray(&dt, &[1.0, 3.0, 4.0], &[1.0, 0.0, 0.0])
+        .unwrap()
+        .unwrap();
+    assert_eq!([4.4, 3.0, 4.0], h.point);
+    assert!((h.distance - 3.4).abs() < 1e-12);
+    //-- along the edges of the grid, through the vertices
+    let h = intersect_ray(&dt, &[0.0, 0.0, 12.0], &[1.0, 1.0, -1.0])
+        .unwrap()
+        .unwrap();
+    assert!(h.point[0] > 4.0 && h.point[0] < 5.0);
+    assert!((h.point[2] - (12.0 - h.point[0])).abs() < 1e-12);
+    let z = startin::interpolation::interpolate(
+        &startin::interpolation::TIN {},
+        &dt,
+        &vec![[h.point[0], h.point[1]]],
+    );
+    assert!((h.point[2] - z[0].as_ref().unwrap()).abs() < 1e-9);
+    assert!(h
+        .triangle
+        .v
+        .iter()
+        .any(|v| dt.get_point(*v).unwrap()[0] == 5.0));
+}